@@ -1,31 +1,68 @@
 //! # The game
 //! A game session consists of four phases
-//! - Setup, [`RunningState::Paused`]
-//! - Running, [`RunningState::Running`] game can be played
+//! - Setup, [`RunningState::Setup`]
+//! - Countdown, [`RunningState::Countdown`] the session is prepared and counts down
+//! - Running, [`RunningState::Running`] game can be played,
+//!   it can be interrupted with [`RunningState::Paused`]
 //! - Finished (menu), [`RunningState::Finished`] game has finished menu is open
 //! - Cleanup, after pressing a menu button the game is set to a "clean" state for restarting or
-//! going back to the main menu
+//!   going back to the main menu
 
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use bevy::time::Stopwatch;
+use bevy::utils::Duration;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
 
 pub const FIELD_SIZE_X: f32 = 500.0;
 pub const FIELD_SIZE_Y: f32 = 500.0;
 pub const SCORE_HEIGHT: f32 = 80.0;
-pub const TILE_NUM_X: usize = 5;
-pub const TILE_NUM_Y: usize = TILE_NUM_X;
-pub const TILE_SIZE_X: f32 = FIELD_SIZE_X / TILE_NUM_X as f32;
-pub const TILE_SIZE_Y: f32 = FIELD_SIZE_Y / TILE_NUM_Y as f32;
-pub const BASE_DELAY: f32 = 0.8;
+/// Margin in pixels around the field in which clicks are ignored
+pub const FIELD_INSET: f32 = 0.0;
+/// Rate in Hz of the fixed timestep advancing spawns and tile decay, in virtual time
+pub const TICK_RATE: f64 = 64.0;
 /// Total time in seconds the game lasts
 pub const GAME_DURATION: f32 = 30.0;
+/// Seconds counted down before a session starts running
+pub const COUNTDOWN: f32 = 3.0;
+/// Remaining seconds of a timed session at which the end is announced
+pub const END_WARNING: f32 = 5.0;
 
+mod anti_cheat;
+mod attract;
+mod bench;
+mod click_log;
+mod debug_overlay;
+mod difficulty;
+mod haptics;
 mod input;
+mod leaderboard;
 mod loading;
+mod replay;
+mod score_audit;
+mod stats;
 
 use crate::despawn_on_screen;
+use crate::settings::{Action, KeyBindings, Palette, Settings, SpawnPattern};
+use crate::storage::Storage;
+use crate::ui::outlined_text;
+pub use anti_cheat::ClickIntervals;
+pub use attract::AttractMode;
+pub use bench::{bench_ai, headless_app, BenchSummary};
+#[cfg(test)]
+use bench::{run_until, start_session};
+use click_log::{ClickLog, ClickLogging, ClickOutcome, ClickRecord};
+pub use debug_overlay::{DebugOverlay, TileCoordinates};
+pub use difficulty::{Difficulty, MultiSpawn, Ramp, SpeedCurve, SpeedSmoothing, TileTiming};
 use input::ClickEvent;
-pub use loading::{Assets, LoadingPlugin};
+pub use input::GamepadInput;
+pub use leaderboard::Leaderboard;
+pub use loading::{Assets, LoadErrors, LoadingPlugin};
+pub use replay::{Replay, ReplayPlayback, SessionTicks};
+use score_audit::{ScoreAudit, ScoreEntry, ScoreSource};
+pub use stats::{LifetimeStats, ReactionBounds};
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 pub enum GameState {
@@ -33,20 +70,47 @@ pub enum GameState {
     Loading,
     Menu,
     Game,
+    /// Overview of the [`LifetimeStats`]
+    Stats,
+    /// Changing the [`Settings`]
+    Settings,
+    /// Overview of the [`Leaderboard`]
+    Leaderboard,
+    /// Explanation of the rules
+    Help,
+    /// Assets listed in [`LoadErrors`] could not be loaded
+    LoadError,
 }
 
+/// Transitions which start a new session, beginning with the [`RunningState::Countdown`].
+pub const SESSION_STARTS: [OnTransition<RunningState>; 2] = [
+    OnTransition {
+        from: RunningState::Setup,
+        to: RunningState::Countdown,
+    },
+    OnTransition {
+        from: RunningState::Finished,
+        to: RunningState::Countdown,
+    },
+];
+
 /// Indicate the state during [`GameState::Game`].
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 pub enum RunningState {
     /// Not used except for starting state
     #[default]
-    Paused,
+    Setup,
+    /// Session is set up, counting down [`COUNTDOWN`] seconds before it runs
+    Countdown,
     /// Game is running, player can interact with tiles
     Running,
+    /// Game is interrupted by the player, time stands still until resuming
+    Paused,
     /// Game is finished, only interact with menu
     Finished,
 }
 
+/// Plugin running the sessions, requires the [`LoadingPlugin`] for its assets
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
@@ -55,18 +119,55 @@ impl Plugin for GamePlugin {
         let bottom_right = Vec2::new(FIELD_SIZE_X, FIELD_SIZE_Y + SCORE_HEIGHT);
         let input_plugin = input::InputPlugin::new(
             RunningState::Running,
-            UVec2::new(TILE_NUM_X as u32, TILE_NUM_Y as u32),
+            GridConfig::default().size(),
             top_left,
             bottom_right,
-        );
+        )
+        .with_inset(FIELD_INSET)
+        .with_pause(RunningState::Paused)
+        .with_countdown(RunningState::Countdown);
+        let high_score: HighScore = Storage::of(app).load(HighScore::KEY).unwrap_or_default();
         app.init_state::<GameState>()
             .init_state::<RunningState>()
             .add_plugins(input_plugin)
+            .add_plugins(stats::StatsPlugin)
+            .add_plugins(leaderboard::LeaderboardPlugin)
+            .add_plugins(click_log::ClickLogPlugin)
+            .add_plugins(score_audit::ScoreAuditPlugin)
+            .add_plugins(replay::ReplayPlugin)
+            .add_plugins(attract::AttractPlugin)
+            .add_plugins(debug_overlay::DebugOverlayPlugin)
+            .add_plugins(haptics::HapticsPlugin)
+            .add_plugins(anti_cheat::AntiCheatPlugin)
             .add_event::<SpawnNewEvent>()
             .add_event::<SoundEvent>()
+            .add_event::<GameOverEvent>()
+            .add_event::<TileExpiredEvent>()
+            .init_resource::<TileExpiry>()
+            .init_resource::<EventLimits>()
+            .init_resource::<SpeedScaledScoring>()
+            .init_resource::<SpawnPaused>()
+            .init_resource::<ScoreFormula>()
+            .init_resource::<WarmupForgiveness>()
+            .init_resource::<SpecialTiles>()
+            .init_resource::<GameMode>()
+            .init_resource::<SpeedSmoothing>()
+            .init_resource::<Ramp>()
+            .init_resource::<Endless>()
+            .init_resource::<TargetScore>()
+            .init_resource::<Difficulty>()
+            .init_resource::<GridConfig>()
+            .insert_resource(RngSeed::from_env())
+            .insert_resource(high_score)
             .insert_resource(Msaa::Off)
+            .insert_resource(Time::<Fixed>::from_hz(TICK_RATE))
             .add_systems(OnEnter(GameState::Game), setup_game)
             .add_systems(PostUpdate, play_sound.run_if(in_state(GameState::Game)))
+            .add_systems(
+                Update,
+                (fade_sprites, animate_spawn, animate_popups, shake_camera)
+                    .run_if(in_state(GameState::Game)),
+            )
             .add_systems(
                 OnExit(GameState::Game),
                 (
@@ -75,28 +176,83 @@ impl Plugin for GamePlugin {
                     cleanup,
                 ),
             )
-            .add_systems(OnEnter(RunningState::Running), setup_session)
             .add_systems(
-                PreUpdate,
-                update_game_time.run_if(in_state(RunningState::Running)),
+                Update,
+                toggle_pause.run_if(
+                    in_state(RunningState::Running).or_else(in_state(RunningState::Paused)),
+                ),
+            )
+            .add_systems(
+                Update,
+                pause_on_focus_loss.run_if(in_state(RunningState::Running)),
+            )
+            .add_systems(
+                Update,
+                resume_on_click.run_if(in_state(RunningState::Paused)),
+            )
+            .add_systems(
+                Update,
+                tick_countdown.run_if(in_state(RunningState::Countdown)),
+            )
+            .add_systems(OnExit(RunningState::Countdown), start_running)
+            .add_systems(OnEnter(RunningState::Paused), pause)
+            .add_systems(OnExit(RunningState::Paused), resume)
+            // The whole simulation steps in a fixed order, so a session only depends on the steps
+            // its clicks were handled in and not on the frame rate, see `Replay`
+            .add_systems(
+                FixedUpdate,
+                (
+                    update_game_time,
+                    click,
+                    tick_spawn_timer,
+                    tile_spawn_timer,
+                    shuffle_tiles,
+                    spawn_tile,
+                    update_tile_points,
+                    move_tiles,
+                    expire_tiles,
+                )
+                    .chain()
+                    .run_if(in_state(RunningState::Running)),
             )
             .add_systems(
                 PostUpdate,
-                spawn_tile.run_if(in_state(RunningState::Running)),
+                game_over.run_if(in_state(RunningState::Running)),
             )
             .add_systems(
                 Update,
-                (click, update_score, tile_spawn_timer, update_tile_points)
+                (
+                    update_score,
+                    metronome,
+                    stop_practice,
+                    (
+                        update_trainer_hint,
+                        update_tile_values,
+                        update_decay_overlays,
+                        update_tile_colors,
+                        flash_endangered_tile,
+                    )
+                        .chain(),
+                )
                     .run_if(in_state(RunningState::Running)),
             )
+            .add_systems(
+                OnEnter(RunningState::Finished),
+                (stop_time, record_high_score, shake_on_misclick),
+            )
             .add_systems(
                 OnExit(RunningState::Finished),
                 (despawn_on_screen::<OnSessionScreen>, cleanup_session),
             );
+        for start in SESSION_STARTS {
+            app.add_systems(start, setup_session);
+        }
     }
 }
 
-/// The elapsed time of a game.
+/// The elapsed real time of a game.
+/// It advances with each fixed step by the real time the step stands for at the current relative
+/// speed, so it only depends on the number of steps and not on the frame rate.
 #[derive(Debug, Resource)]
 struct GameTime(Stopwatch);
 
@@ -104,13 +260,270 @@ struct GameTime(Stopwatch);
 #[derive(Debug, Resource)]
 struct SpawnTimer(Timer);
 
-/// Timer to disable first 0.8s of menu interaction
+/// Points collected in the current session
+#[derive(Debug, Default, Resource)]
+pub struct Score(pub usize);
+
+/// Whether the [`END_WARNING`] was given this session, it is not repeated after a time bonus
+#[derive(Debug, Default, Resource)]
+struct EndWarned(bool);
+
+/// Misclicks left in the session, the session ends when the last one is lost.
+/// Starts at [`Difficulty::lives`].
+#[derive(Debug, Default, Resource)]
+pub struct Lives(pub usize);
+
+/// Time added to [`GAME_DURATION`] by [`TileKind::TimeBonus`] tiles during the session
+#[derive(Debug, Default, Resource)]
+struct ExtraTime(Duration);
+
+impl ExtraTime {
+    /// Add `bonus` without exceeding `max` in total, returns the time actually added
+    fn add(&mut self, bonus: Duration, max: Duration) -> Duration {
+        let added = bonus.min(max.saturating_sub(self.0));
+        self.0 += added;
+        added
+    }
+
+    /// Seconds the session lasts
+    fn duration(&self) -> f32 {
+        GAME_DURATION + self.0.as_secs_f32()
+    }
+}
+
+/// Time left before the session starts, see [`RunningState::Countdown`]
+#[derive(Debug, Resource)]
+pub struct Countdown(pub Timer);
+
+/// Best [`Score`] of all sessions, persisted between runs
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, Resource)]
+pub struct HighScore(pub usize);
+
+impl HighScore {
+    /// Storage key
+    const KEY: &'static str = "high_score";
+}
+
+/// Tiles cleared in quick succession, reset by slow hits, misclicks and expired tiles
+#[derive(Debug, Default, Resource)]
+struct Combo {
+    count: usize,
+    /// Session time of the previous hit
+    last_hit: Option<Duration>,
+}
+
+impl Combo {
+    /// Maximum time between two hits continuing the combo
+    const WINDOW: Duration = Duration::from_millis(600);
+    /// Score multiplier added per combo step
+    const STEP: f32 = 0.1;
+    /// Upper bound of the score multiplier
+    const MAX_MULTIPLIER: f32 = 2.0;
+    /// Relative pitch of the hit sound added per combo step
+    const PITCH_STEP: f32 = 0.05;
+    /// Upper bound of the relative pitch, higher gets shrill
+    const MAX_PITCH: f32 = 1.5;
+
+    /// Register a hit at session time `now`
+    fn hit(&mut self, now: Duration) {
+        let rapid = self
+            .last_hit
+            .is_some_and(|last| now.saturating_sub(last) <= Self::WINDOW);
+        self.count = if rapid { self.count + 1 } else { 0 };
+        self.last_hit = Some(now);
+    }
+
+    /// Break the combo
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Combo which is still running at session time `now`
+    fn current(&self, now: Duration) -> usize {
+        match self.last_hit {
+            Some(last) if now.saturating_sub(last) <= Self::WINDOW => self.count,
+            _ => 0,
+        }
+    }
+
+    /// Score multiplier of the combo
+    fn multiplier(&self) -> f32 {
+        (1.0 + self.count as f32 * Self::STEP).min(Self::MAX_MULTIPLIER)
+    }
+
+    /// Relative pitch of the hit sound, rising with the combo
+    fn pitch(&self) -> f32 {
+        (1.0 + self.count as f32 * Self::PITCH_STEP).min(Self::MAX_PITCH)
+    }
+}
+
+/// Seed of the random number generator of every session, `None` for a random seed.
+/// Read from the environment variable [`RngSeed::ENV`], sessions with the same seed
+/// spawn the same tiles as long as the player acts the same.
+#[derive(Debug, Default, Resource)]
+pub struct RngSeed(pub Option<u64>);
+
+impl RngSeed {
+    /// Environment variable overriding the seed
+    const ENV: &'static str = "TILE_CLICKER_SEED";
+
+    fn from_env() -> Self {
+        let Ok(value) = std::env::var(Self::ENV) else {
+            return Self(None);
+        };
+        match value.parse() {
+            Ok(seed) => Self(Some(seed)),
+            Err(e) => {
+                warn!("Ignoring invalid {} {:?}: {}", Self::ENV, value, e);
+                Self(None)
+            }
+        }
+    }
+}
+
+/// Random number generator of the session, all random decisions of the game are drawn from it
 #[derive(Debug, Resource)]
-struct MenuActiveDelay(Timer);
+pub struct GameRng {
+    /// Seed the generator started with, shown to share the session
+    pub seed: u64,
+    rng: StdRng,
+}
+
+impl GameRng {
+    fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+/// Performance of the player during the session, shown at game over
+#[derive(Debug, Default, Resource)]
+pub struct SessionStats {
+    /// Tiles clicked in time
+    pub hits: usize,
+    /// Clicks on empty tiles, including forgiven ones
+    pub misses: usize,
+    /// Longest [`Combo`] of the session
+    pub max_combo: usize,
+    /// Tiles which lost all of their points
+    pub tiles_expired: usize,
+}
+
+impl SessionStats {
+    /// Share of clicks which hit a tile in percent, `None` without clicks
+    pub fn accuracy(&self) -> Option<f32> {
+        let clicks = self.hits + self.misses;
+        (clicks > 0).then(|| self.hits as f32 / clicks as f32 * 100.0)
+    }
+}
 
-/// The score of player
+/// Time it took the player to click each cleared tile during the session
 #[derive(Debug, Default, Resource)]
-struct Score(usize);
+pub struct ReactionTimes(pub Vec<Duration>);
+
+impl ReactionTimes {
+    /// Width of a single histogram bucket
+    pub const BUCKET_WIDTH: Duration = Duration::from_millis(200);
+    /// Number of histogram buckets, the last bucket contains all slower reactions
+    pub const BUCKETS: usize = 6;
+
+    /// Count the reaction times per bucket of [`Self::BUCKET_WIDTH`]
+    pub fn histogram(&self) -> [usize; Self::BUCKETS] {
+        let mut buckets = [0; Self::BUCKETS];
+        for time in &self.0 {
+            let bucket = time.as_millis() / Self::BUCKET_WIDTH.as_millis();
+            buckets[(bucket as usize).min(Self::BUCKETS - 1)] += 1;
+        }
+        buckets
+    }
+}
+
+/// Seconds a clicked tile flashes before it is removed
+const HIT_FLASH: f32 = 0.08;
+
+/// Timer of a [`TileKind::Moving`] tile until its next hop, in virtual time
+#[derive(Debug, Component)]
+struct Moving {
+    timer: Timer,
+}
+
+impl Moving {
+    /// Seconds between two hops
+    const INTERVAL: f32 = 0.75;
+
+    fn new() -> Self {
+        Self {
+            timer: Timer::from_seconds(Self::INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+/// Elapsed [`GameTime`] at the moment a tile was spawned
+#[derive(Debug, Component)]
+struct SpawnedAt(Duration);
+
+/// Sprite which fades out from `alpha` and is removed when the timer finishes
+#[derive(Debug, Component)]
+struct Fading {
+    timer: Timer,
+    alpha: f32,
+}
+
+impl Fading {
+    fn new(secs: f32, alpha: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(secs, TimerMode::Once),
+            alpha,
+        }
+    }
+}
+
+/// Tile which grows from nothing to its full size until the timer finishes
+#[derive(Debug, Component)]
+struct SpawnAnim {
+    timer: Timer,
+}
+
+impl SpawnAnim {
+    /// Seconds the animation lasts
+    const DURATION: f32 = 0.12;
+
+    fn new() -> Self {
+        Self {
+            timer: Timer::from_seconds(Self::DURATION, TimerMode::Once),
+        }
+    }
+}
+
+/// Text which rises and fades out, removed when the timer finishes
+#[derive(Debug, Component)]
+struct Popup {
+    timer: Timer,
+}
+
+impl Popup {
+    /// Seconds a popup is shown
+    const DURATION: f32 = 0.6;
+    /// Distance in pixels a popup rises per second
+    const RISE: f32 = 60.0;
+}
+
+/// Camera which jitters around its origin until the timer finishes
+#[derive(Debug, Component)]
+struct Shake {
+    timer: Timer,
+    /// Maximum offset in pixels, decaying over the duration
+    intensity: f32,
+}
+
+impl Shake {
+    /// Seconds the camera shakes after a misclick
+    const DURATION: f32 = 0.3;
+    /// Maximum offset in pixels after a misclick
+    const INTENSITY: f32 = 6.0;
+}
 
 /// Tile position of the last spawned tile
 #[derive(Debug, Default, Resource)]
@@ -123,6 +536,275 @@ enum SpawnNewEvent {
     Normal,
     /// Tile with position indicating a misclick
     Error((u32, u32)),
+    /// Briefly flash a misclick which does not end the game
+    Flash((u32, u32)),
+    /// Move all tiles to new random cells
+    Shuffle,
+    /// Golden tile worth double points
+    Bonus,
+    /// Blue tile extending the session, see [`SpecialTiles::time_bonus`]
+    TimeBonus,
+}
+
+/// Kinds of tiles on the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TileKind {
+    Normal,
+    /// Clicking it moves all other tiles to new random cells
+    Shuffle,
+    /// Worth double points
+    Bonus,
+    /// Extends the session by [`SpecialTiles::time_bonus`]
+    TimeBonus,
+    /// Hops to a free neighbouring cell every [`Moving::INTERVAL`] seconds,
+    /// only with [`Difficulty::moving_tiles`]
+    Moving,
+}
+
+impl TileKind {
+    /// Color the tile is drawn with
+    fn color(self, palette: &Palette) -> Color {
+        match self {
+            Self::Normal | Self::Moving => palette.tile,
+            Self::Shuffle => palette.shuffle,
+            Self::Bonus => palette.bonus,
+            Self::TimeBonus => palette.time_bonus,
+        }
+    }
+}
+
+/// Chances of spawning special tiles instead of normal tiles
+#[derive(Debug, Resource)]
+pub struct SpecialTiles {
+    /// Chance of a [`TileKind::Shuffle`] tile, none spawn unless it is raised
+    pub shuffle_chance: f64,
+    /// [`Combo`] required before [`TileKind::Shuffle`] tiles can spawn
+    pub shuffle_combo: usize,
+    /// Chance of a [`TileKind::Moving`] tile, only with [`Difficulty::moving_tiles`]
+    pub moving_chance: f64,
+    /// Chance of a normal spawn becoming a [`SpawnNewEvent::Bonus`]
+    pub bonus_chance: f64,
+    /// Chance of a normal spawn becoming a [`SpawnNewEvent::TimeBonus`], only in timed sessions
+    pub time_bonus_chance: f64,
+    /// Time added to the session by clicking a [`TileKind::TimeBonus`] tile
+    pub time_bonus: Duration,
+    /// Maximum time added to a single session
+    pub max_time_bonus: Duration,
+}
+
+impl Default for SpecialTiles {
+    fn default() -> Self {
+        Self {
+            shuffle_chance: 0.0,
+            shuffle_combo: 0,
+            moving_chance: 0.15,
+            bonus_chance: 0.05,
+            time_bonus_chance: 0.02,
+            time_bonus: Duration::from_secs(2),
+            max_time_bonus: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Rules of the session, as selected in the main menu
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Resource)]
+pub enum GameMode {
+    /// Play for [`GAME_DURATION`]
+    #[default]
+    Timed,
+    /// Play for [`GAME_DURATION`], reaching the [`TargetScore`] ends the session with a win
+    Target,
+    /// Play until a misclick instead of for [`GAME_DURATION`], see [`Endless`]
+    Endless,
+    /// Highlight the tile which should be clicked next, as a training aid for beginners.
+    /// Clicking any other tile is still allowed, misclicks and the [`GAME_DURATION`] do not end
+    /// the session, it is stopped with Q instead.
+    Practice,
+    /// Relax: tiles appear at the calm [`GameMode::ZEN_DELAY`] without speeding up, misclicks,
+    /// expiring tiles and the [`GAME_DURATION`] don't end the session and the score and time
+    /// are hidden. It is stopped with Q like a practice session.
+    /// These sessions don't count for the high score and the leaderboard.
+    Zen,
+}
+
+impl GameMode {
+    /// Seconds between timed spawns in [`GameMode::Zen`]
+    pub const ZEN_DELAY: f32 = 1.2;
+
+    /// Whether the session ends after [`GAME_DURATION`], possibly extended by time bonuses
+    fn timed(self) -> bool {
+        matches!(self, Self::Timed | Self::Target)
+    }
+
+    /// Whether the elapsed instead of the remaining time is shown
+    fn counts_up(self) -> bool {
+        matches!(self, Self::Endless | Self::Practice)
+    }
+
+    /// Whether the session only ends when the player stops it
+    fn open_ended(self) -> bool {
+        matches!(self, Self::Practice | Self::Zen)
+    }
+}
+
+/// Score which ends a [`GameMode::Target`] session with a win when reached
+#[derive(Debug, Resource)]
+pub struct TargetScore(pub usize);
+
+impl TargetScore {
+    /// Target used when selecting the target mode in the menu
+    pub const DEFAULT: usize = 250;
+}
+
+impl Default for TargetScore {
+    fn default() -> Self {
+        Self(Self::DEFAULT)
+    }
+}
+
+/// Rules of [`GameMode::Endless`] sessions
+#[derive(Debug, Resource)]
+pub struct Endless {
+    /// Real time after which an endless session ends as completed, `None` for no limit
+    pub max_duration: Option<Duration>,
+}
+
+impl Default for Endless {
+    fn default() -> Self {
+        Self {
+            max_duration: Some(Duration::from_secs(10 * 60)),
+        }
+    }
+}
+
+impl Endless {
+    /// Cap of the relative speed, endless sessions don't ramp up further on any difficulty
+    pub const MAX_SPEED: f32 = 3.0;
+}
+
+/// Marker of the number showing the points of a tile
+#[derive(Debug, Component)]
+struct TileValue;
+
+/// Marker of the square on a tile showing its remaining points, see [`Settings::decay_overlay`]
+#[derive(Debug, Component)]
+struct DecayOverlay;
+
+/// Marker of the highlight behind the suggested tile of [`GameMode::Practice`]
+#[derive(Debug, Component)]
+struct TrainerHint;
+
+/// Multiply the points of a tile with the relative speed of [`Time<Virtual>`],
+/// compensating for tiles losing points faster later in the game
+#[derive(Debug, Default, Resource)]
+pub struct SpeedScaledScoring(pub bool);
+
+/// How the points of a tile follow the time it has left, see [`TileTiming::decay_secs`].
+/// A fresh tile is worth two points per second of decay with every formula.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub enum ScoreFormula {
+    /// Two points per second left
+    #[default]
+    Linear,
+    /// Proportional to the square of the fraction of time left, rewarding fast clicks more
+    Quadratic,
+    /// Full points dropping in [`ScoreFormula::STEPS`] equal steps
+    Stepped,
+}
+
+impl ScoreFormula {
+    /// Number of steps of [`ScoreFormula::Stepped`]
+    pub const STEPS: f32 = 3.0;
+
+    /// Points a tile is currently worth, given its point timer on the [`Grid`]
+    fn points(self, timer: &Timer) -> usize {
+        let max = timer.duration().as_secs_f32() * 2.0;
+        let fraction = timer.fraction_remaining();
+        let points = match self {
+            Self::Linear => timer.remaining_secs() * 2.0,
+            Self::Quadratic => max * fraction.powi(2),
+            Self::Stepped => max * (fraction * Self::STEPS).ceil() / Self::STEPS,
+        };
+        points.trunc() as usize
+    }
+}
+
+/// Stop new tiles from appearing while the tiles on the field and the clock keep running,
+/// e.g. for a breather. Misclick feedback is still shown.
+#[derive(Debug, Default, Resource)]
+pub struct SpawnPaused(pub bool);
+
+/// Seconds at the start of a session during which misclicks don't end the game
+#[derive(Debug, Default, Resource)]
+pub struct WarmupForgiveness(pub f32);
+
+/// Why a session ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Resource)]
+pub enum GameOverReason {
+    /// The game duration elapsed
+    TimeUp,
+    /// An empty tile was clicked
+    Misclick,
+    /// The [`TargetScore`] was reached
+    TargetReached,
+    /// A tile lost all of its points with [`Difficulty::fatal_expiry`]
+    TileExpired,
+    /// The [`Endless::max_duration`] elapsed
+    Completed,
+    /// The player stopped a [`GameMode::Practice`] or [`GameMode::Zen`] session
+    Stopped,
+}
+
+/// End the current session
+#[derive(Debug, Event)]
+pub struct GameOverEvent(pub GameOverReason);
+
+/// Maximum number of events handled per fixed step.
+/// Input buffered during a lag spike arrives in a single frame, handling all of it at once
+/// could cascade into clearing many tiles or ending the game.
+#[derive(Debug, Resource)]
+pub struct EventLimits {
+    /// Clicks handled per step
+    pub clicks: usize,
+    /// Normal tiles spawned per step, multiplied by the current [`MultiSpawn`] count
+    pub spawns: usize,
+}
+
+impl Default for EventLimits {
+    fn default() -> Self {
+        Self {
+            clicks: 4,
+            spawns: 1,
+        }
+    }
+}
+
+/// A tile on the grid became worth zero points
+#[derive(Debug, Event)]
+pub struct TileExpiredEvent {
+    pub x: usize,
+    pub y: usize,
+    pub entity: Entity,
+}
+
+/// What happens when a tile is no longer worth any points,
+/// [`Difficulty::fatal_expiry`] additionally ends the session.
+/// Tiles with a [`TileTiming::lifetime_secs`] are only removed at the end of their lifetime.
+#[derive(Debug, Resource)]
+pub struct TileExpiry {
+    /// Remove the tile from the field, otherwise it stays worth zero points until clicked
+    pub despawn: bool,
+    /// Points subtracted from the score
+    pub penalty: usize,
+}
+
+impl Default for TileExpiry {
+    fn default() -> Self {
+        Self {
+            despawn: true,
+            penalty: 0,
+        }
+    }
 }
 
 /// Possible sounds to play
@@ -130,6 +812,16 @@ enum SpawnNewEvent {
 enum SoundEvent {
     Normal,
     Error,
+    /// A tile spawned, pitched with the relative speed of [`Time<Virtual>`]
+    Spawn,
+    /// Beat of the [`Settings::metronome`]
+    Tick,
+    /// A [`TileKind::Bonus`] tile was clicked
+    Bonus,
+    /// A [`TileKind::TimeBonus`] tile was clicked
+    TimeBonus,
+    /// Only [`END_WARNING`] seconds of the session are left
+    Warning,
 }
 
 /// Tag for entities in [`GameState::Game`]
@@ -141,98 +833,298 @@ pub struct OnGameScreen;
 pub struct OnSessionScreen;
 
 /// Tag indicating score text
-#[derive(Debug, Component)]
+#[derive(Debug, Clone, Component)]
 struct ScoreText;
 
-/// Grid configuration for the game
-type GameGrid = Grid<TILE_NUM_X, TILE_NUM_Y>;
+/// Size of the field in tiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Resource)]
+pub struct GridConfig {
+    pub cols: usize,
+    pub rows: usize,
+}
+
+impl GridConfig {
+    /// Board sizes which can be selected in the menu
+    pub const PRESETS: &'static [Self] = &[
+        Self { cols: 3, rows: 3 },
+        Self { cols: 5, rows: 5 },
+        Self { cols: 7, rows: 7 },
+    ];
+
+    /// The next preset, wrapping around to the smallest
+    pub fn next(self) -> Self {
+        let i = Self::PRESETS.iter().position(|c| *c == self);
+        Self::PRESETS[i.map_or(0, |i| (i + 1) % Self::PRESETS.len())]
+    }
+
+    /// Number of tiles in each direction
+    pub fn size(&self) -> UVec2 {
+        UVec2::new(self.cols as u32, self.rows as u32)
+    }
+
+    /// Size of a single tile in world units
+    fn tile_size(&self) -> Vec2 {
+        Vec2::new(FIELD_SIZE_X, FIELD_SIZE_Y) / self.size().as_vec2()
+    }
+
+    /// Convert a position in cell units to world coordinates.
+    /// The origin is the top left corner of the field, `(0.5, 0.5)` is the center of the first
+    /// tile. This matches the cell boundaries used for converting clicks to tiles.
+    fn cell_to_world(&self, cell: Vec2) -> Vec2 {
+        let tile_size = self.tile_size();
+        let x = -FIELD_SIZE_X / 2.0 + cell.x * tile_size.x;
+        let y = -FIELD_SIZE_Y / 2.0 + cell.y * tile_size.y;
+        Vec2::new(x, -y - SCORE_HEIGHT / 2.0)
+    }
+}
+
+impl Default for GridConfig {
+    fn default() -> Self {
+        Self { cols: 5, rows: 5 }
+    }
+}
+
+/// Entity, point timer, lifetime timer and kind of a tile on the [`Grid`]
+type GridTile = (Entity, Timer, Option<Timer>, TileKind);
 
 /// A grid indicating which tiles exist.
-/// The grid has X elements in X direction and Y elements in Y direction.
+/// The tiles are stored row by row, the tile at `x`, `y` has index `y * cols + x`.
 /// The [`bevy::ecs::entity::Entity`] is the entity containing all the components of the Tile.
-/// The [`bevy::time::Timer`] is for tracking how many points a tile is worth.
+/// The first [`bevy::time::Timer`] is for tracking how many points a tile is worth,
+/// the optional second one for removing the tile after [`TileTiming::lifetime_secs`].
 #[derive(Debug, Clone, Resource)]
-struct Grid<const X: usize, const Y: usize> {
-    tiles: [[Option<(Entity, Timer)>; X]; Y],
+struct Grid {
+    config: GridConfig,
+    timing: TileTiming,
+    formula: ScoreFormula,
+    tiles: Vec<Option<GridTile>>,
 }
 
-impl<const X: usize, const Y: usize> Grid<X, Y> {
+impl Grid {
     /// New instance of an empty grid
-    fn new() -> Self {
-        let tiles = std::array::from_fn(|_| std::array::from_fn(|_| None));
-        Self { tiles }
+    fn new(config: GridConfig, timing: TileTiming) -> Self {
+        let tiles = vec![None; config.cols * config.rows];
+        Self {
+            config,
+            timing,
+            formula: ScoreFormula::default(),
+            tiles,
+        }
+    }
+
+    /// Index of a position, positions outside the grid are clamped to the border
+    fn index(&self, x: usize, y: usize) -> usize {
+        let x = x.min(self.config.cols - 1);
+        let y = y.min(self.config.rows - 1);
+        y * self.config.cols + x
+    }
+
+    /// Position of an index
+    fn position(&self, index: usize) -> UVec2 {
+        let cols = self.config.cols;
+        UVec2::new((index % cols) as u32, (index / cols) as u32)
     }
 
     /// Set a tile.
-    fn set(&mut self, x: usize, y: usize, entity: Entity) {
-        let x = x.min(X - 1);
-        let y = y.min(Y - 1);
-        self.tiles[y][x] = Some((entity, Timer::from_seconds(5.0, TimerMode::Once)));
+    fn set(&mut self, x: usize, y: usize, entity: Entity, kind: TileKind) {
+        let i = self.index(x, y);
+        let timer = Timer::from_seconds(self.timing.decay_secs, TimerMode::Once);
+        let lifetime = self
+            .timing
+            .lifetime_secs
+            .map(|secs| Timer::from_seconds(secs, TimerMode::Once));
+        self.tiles[i] = Some((entity, timer, lifetime, kind));
     }
 
     /// Check if a tile is free
     fn is_free(&self, x: usize, y: usize) -> bool {
-        self.tiles[y][x].is_none()
+        self.tiles[self.index(x, y)].is_none()
+    }
+
+    /// Move the tile at `from` to the free cell `to`, keeping its timers and kind
+    fn move_tile(&mut self, from: UVec2, to: UVec2) {
+        let from = self.index(from.x as usize, from.y as usize);
+        let to = self.index(to.x as usize, to.y as usize);
+        if self.tiles[to].is_none() {
+            self.tiles[to] = self.tiles[from].take();
+        }
+    }
+
+    /// Position of the tile of an entity which is still worth points
+    fn position_of_scoring(&self, entity: Entity) -> Option<UVec2> {
+        let i = self.tiles.iter().position(|tile| {
+            tile.as_ref()
+                .is_some_and(|t| t.0 == entity && !t.1.finished())
+        })?;
+        Some(self.position(i))
+    }
+
+    /// Free cells next to a position, not counting diagonals
+    fn free_neighbours(&self, pos: UVec2) -> Vec<UVec2> {
+        let GridConfig { cols, rows } = self.config;
+        [(-1, 0), (1, 0), (0, -1), (0, 1)]
+            .into_iter()
+            .filter_map(|(dx, dy)| {
+                let x = pos.x.checked_add_signed(dx)?;
+                let y = pos.y.checked_add_signed(dy)?;
+                ((x as usize) < cols && (y as usize) < rows).then_some(UVec2::new(x, y))
+            })
+            .filter(|cell| self.is_free(cell.x as usize, cell.y as usize))
+            .collect()
     }
 
     /// Remove a tile from the grid.
-    /// Returns the entity and the score of the tile.
-    fn take(&mut self, x: usize, y: usize) -> Option<(Entity, usize)> {
-        let x = x.min(X - 1);
-        let y = y.min(Y - 1);
-        let score = |t: Timer| (t.remaining_secs() * 2.0).trunc() as usize;
-        self.tiles[y][x].take().map(|(e, t)| (e, score(t)))
+    /// Returns the entity, the score and the kind of the tile.
+    fn take(&mut self, x: usize, y: usize) -> Option<(Entity, usize, TileKind)> {
+        let i = self.index(x, y);
+        let formula = self.formula;
+        self.tiles[i]
+            .take()
+            .map(|(e, t, _, k)| (e, formula.points(&t), k))
+    }
+
+    /// Points of a freshly spawned tile
+    fn max_points(&self) -> usize {
+        self.formula.points(&Timer::from_seconds(
+            self.timing.decay_secs,
+            TimerMode::Once,
+        ))
+    }
+
+    /// Entities of all tiles with the points they are currently worth
+    fn values(&self) -> impl Iterator<Item = (Entity, usize)> + '_ {
+        self.tiles
+            .iter()
+            .flatten()
+            .map(|(e, t, _, _)| (*e, self.formula.points(t)))
+    }
+
+    /// Entities of all tiles with the fraction of their time which is left
+    fn fractions(&self) -> impl Iterator<Item = (Entity, f32)> + '_ {
+        self.tiles
+            .iter()
+            .flatten()
+            .map(|(e, t, _, _)| (*e, t.fraction_remaining()))
+    }
+
+    /// Move all tiles to random cells, keeping their timers and kinds.
+    /// Returns the entities with their new position.
+    fn shuffle(&mut self, rng: &mut impl rand::Rng) -> Vec<(Entity, UVec2)> {
+        use rand::seq::SliceRandom;
+        let tiles: Vec<_> = self.tiles.iter_mut().filter_map(|t| t.take()).collect();
+        let mut cells: Vec<_> = (0..self.tiles.len()).collect();
+        cells.shuffle(rng);
+        let mut moved = Vec::with_capacity(tiles.len());
+        for (tile, cell) in tiles.into_iter().zip(cells) {
+            moved.push((tile.0, self.position(cell)));
+            self.tiles[cell] = Some(tile);
+        }
+        moved
     }
 
     /// Update the timers of the tiles.
     /// This should be called every frame until the game ends.
-    fn tick(&mut self, delta: bevy::utils::Duration) {
-        let iter = self.tiles.iter_mut().flat_map(|row| row.iter_mut());
-        for tile in iter {
-            tile.as_mut().map(|(_, t)| t.tick(delta));
+    /// Returns the position and entity of tiles which are worth zero points since this tick,
+    /// or which reached their lifetime before that.
+    /// Tiles which reached their lifetime are removed and returned as second list,
+    /// their entities have to be despawned.
+    fn tick(&mut self, delta: Duration) -> (Vec<(UVec2, Entity)>, Vec<Entity>) {
+        let mut expired = Vec::new();
+        let mut vanished = Vec::new();
+        for (i, tile) in self.tiles.iter_mut().enumerate() {
+            let Some((entity, timer, lifetime, _)) = tile else {
+                continue;
+            };
+            if timer.tick(delta).just_finished() {
+                expired.push((i, *entity));
+            }
+            let Some(lifetime) = lifetime else {
+                continue;
+            };
+            if lifetime.tick(delta).just_finished() {
+                // A tile vanishing while still worth points counts as expired as well
+                if !timer.finished() {
+                    expired.push((i, *entity));
+                }
+                vanished.push(*entity);
+                *tile = None;
+            }
         }
+        let expired = expired
+            .into_iter()
+            .map(|(i, entity)| (self.position(i), entity))
+            .collect();
+        (expired, vanished)
     }
 
-    /// Check if all tiles are occupied.
-    fn is_full(&self) -> bool {
-        self.free_tiles() == 0
+    /// Entity of the tile at a position
+    fn get(&self, x: usize, y: usize) -> Option<Entity> {
+        self.tiles[self.index(x, y)].as_ref().map(|(e, _, _, _)| *e)
     }
 
-    /// Number of free tiles available on the grid.
-    fn free_tiles(&self) -> usize {
+    /// Position and tile closest to losing all of its points
+    fn most_urgent(&self) -> Option<(UVec2, &GridTile)> {
         self.tiles
             .iter()
-            .flat_map(|row| row.iter())
-            .filter(|tile| tile.is_none())
-            .count()
+            .enumerate()
+            .filter_map(|(i, tile)| {
+                let tile = tile.as_ref()?;
+                (!tile.1.finished()).then_some((self.position(i), tile))
+            })
+            .min_by_key(|(_, (_, timer, _, _))| timer.remaining())
+    }
+
+    /// Check if all tiles are occupied.
+    fn is_full(&self) -> bool {
+        self.free_tiles() == 0
+    }
+
+    /// Number of free tiles available on the grid.
+    fn free_tiles(&self) -> usize {
+        self.tiles.iter().filter(|tile| tile.is_none()).count()
     }
 
     /// Number of occupied tiles on the grid.
     fn filled_tiles(&self) -> usize {
-        (X * Y) - self.free_tiles()
+        self.tiles.len() - self.free_tiles()
     }
 }
 
 /// Configure all game things not associated with an specific session
+#[allow(clippy::too_many_arguments)]
 fn setup_game(
     mut commands: Commands,
     assets: Res<Assets>,
+    settings: Res<Settings>,
+    palette: Res<Palette>,
+    speed_scaled: Res<SpeedScaledScoring>,
+    mode: Res<GameMode>,
+    difficulty: Res<Difficulty>,
+    config: Res<GridConfig>,
+    mut input_grid: ResMut<input::Grid>,
     mut state: ResMut<NextState<RunningState>>,
 ) {
     info!("Setup Game");
     commands.spawn((Camera2dBundle::default(), OnGameScreen));
+    input_grid.size = config.size();
 
     // Fill field with tile pattern
     // TODO: try shader for tile pattern
-    for y in 0..TILE_NUM_Y {
-        for x in 0..TILE_NUM_X {
-            tile::<OnGameScreen>(
-                &mut commands,
-                UVec3::new(x as u32, y as u32, 0),
-                Color::rgb(0.8, 0.8, 0.8),
-            );
+    let size = config.size();
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let color = settings.background_pattern.color(&palette, x, y, size.y);
+            tile::<OnGameScreen>(&mut commands, &config, UVec3::new(x, y, 0), color);
         }
     }
+    if settings.grid_lines {
+        grid_lines(&mut commands, &config, palette.grid_line);
+    }
+    // Transision from Setup to Countdown state
+    state.set(RunningState::Countdown);
+    if *mode == GameMode::Zen {
+        return;
+    }
     // Score and remaining time UI
     let font: Handle<Font> = assets.font.clone();
     fn text_section(s: &str, font: Handle<Font>) -> TextSection {
@@ -245,62 +1137,366 @@ fn setup_game(
             },
         }
     }
-    commands
-        .spawn(TextBundle {
-            text: Text::from_sections([
-                text_section("Score: ", font.clone()),
-                text_section("0", font.clone()),
-                text_section(" Time: ", font.clone()),
-                text_section(&GAME_DURATION.to_string(), font.clone()),
-            ]),
-            ..default()
-        })
-        .insert(ScoreText)
-        .insert(OnGameScreen);
-
-    // Transision from Paused to Running state
-    state.set(RunningState::Running);
+    // Endless and practice sessions show the elapsed instead of the remaining time
+    let (time_label, start_time) = if mode.counts_up() {
+        (" Elapsed: ", 0.0)
+    } else {
+        (" Time: ", GAME_DURATION)
+    };
+    let mut text = Text::from_sections([
+        text_section("Score: ", font.clone()),
+        text_section("0", font.clone()),
+        text_section(time_label, font.clone()),
+        text_section(&start_time.to_string(), font.clone()),
+        text_section("", font.clone()),
+        text_section(&lives_label(difficulty.lives(), *difficulty), font.clone()),
+    ]);
+    if speed_scaled.0 {
+        text.sections.push(text_section(" x1.0", font.clone()));
+    }
+    let score_text = outlined_text(
+        &mut commands,
+        text,
+        default(),
+        settings.text_outline,
+        ScoreText,
+    );
+    commands.entity(score_text).insert(OnGameScreen);
 }
 
 /// Setup session specific resources
-fn setup_session(mut commands: Commands, mut time: ResMut<Time<Virtual>>) {
-    info!("Setup Session");
-    commands.insert_resource(GameGrid::new());
+#[allow(clippy::too_many_arguments)]
+fn setup_session(
+    mut commands: Commands,
+    mut time: ResMut<Time<Virtual>>,
+    difficulty: Res<Difficulty>,
+    palette: Res<Palette>,
+    mode: Res<GameMode>,
+    config: Res<GridConfig>,
+    seed: Res<RngSeed>,
+    playback: Option<Res<ReplayPlayback>>,
+    formula: Res<ScoreFormula>,
+    settings: Res<Settings>,
+) {
+    // A replay only plays back correctly with the seed it was recorded with
+    let seed = playback
+        .map(|playback| playback.seed())
+        .or(seed.0)
+        .unwrap_or_else(rand::random);
+    info!("Setup Session with seed {}", seed);
+    let mut game_rng = GameRng::new(seed);
+    let rng = &mut game_rng.rng;
+    // Start with tiles which already lost some points, as if they were spawned earlier
+    let mut grid = Grid::new(*config, difficulty.tile_timing());
+    grid.formula = *formula;
+    let mut last_spawn = UVec2::default();
+    for _ in 0..difficulty.initial_tiles() {
+        let Some(pos) = spawn_position(&grid, last_spawn, settings.spawn_pattern, rng) else {
+            break;
+        };
+        let entity = tile::<OnSessionScreen>(&mut commands, &config, pos.extend(1), palette.tile);
+        commands.entity(entity).insert(SpawnedAt(Duration::ZERO));
+        let (_, vanished) = grid.tick(Duration::from_secs_f32(difficulty.spawn_delay()));
+        for entity in vanished {
+            commands.entity(entity).despawn_recursive();
+        }
+        grid.set(pos.x as usize, pos.y as usize, entity, TileKind::Normal);
+        last_spawn = pos;
+    }
+    commands.insert_resource(grid);
+    if *mode == GameMode::Practice {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: palette.accent,
+                    custom_size: Some(config.tile_size()),
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            TrainerHint,
+            OnSessionScreen,
+        ));
+    }
+    let mut curve = difficulty.speed_curve();
+    if *mode == GameMode::Endless {
+        curve.max_speed = curve.max_speed.min(Endless::MAX_SPEED);
+    }
+    info!(
+        "Speed plateau of {:.2} after {:.1}s",
+        curve.max_speed,
+        curve.plateau_start()
+    );
+    commands.insert_resource(curve);
+    if *mode == GameMode::Zen {
+        // A single tile at a time, however long the session lasts
+        commands.insert_resource(MultiSpawn {
+            start: f32::INFINITY,
+            interval: 1.0,
+            max: 1,
+        });
+    } else {
+        commands.insert_resource(difficulty.multi_spawn());
+    }
     commands.insert_resource(GameTime(Stopwatch::new()));
     commands.insert_resource(Score(0));
-    commands.insert_resource(LastSpawn(UVec2::default()));
+    commands.insert_resource(Combo::default());
+    commands.insert_resource(SessionStats::default());
+    commands.insert_resource(ExtraTime::default());
+    commands.insert_resource(EndWarned::default());
+    commands.insert_resource(Lives(difficulty.lives()));
+    commands.insert_resource(game_rng);
+    commands.insert_resource(ReactionTimes::default());
+    commands.insert_resource(LastSpawn(last_spawn));
+    let spawn_delay = if *mode == GameMode::Zen {
+        GameMode::ZEN_DELAY
+    } else {
+        difficulty.spawn_delay()
+    };
     commands.insert_resource(SpawnTimer(Timer::from_seconds(
-        BASE_DELAY,
+        spawn_delay,
         TimerMode::Repeating,
     )));
-    time.unpause();
+    commands.insert_resource(Countdown(Timer::from_seconds(COUNTDOWN, TimerMode::Once)));
+    // Time stands still until the countdown finished
+    time.pause();
     time.set_relative_speed(1.0);
 }
 
+/// Count down before the session runs, in real time as the virtual time is paused
+fn tick_countdown(
+    mut countdown: ResMut<Countdown>,
+    time: Res<Time<Real>>,
+    mut state: ResMut<NextState<RunningState>>,
+) {
+    if countdown.0.tick(time.delta()).just_finished() {
+        state.set(RunningState::Running);
+    }
+}
+
+/// Start the time of the session after the countdown
+fn start_running(mut time: ResMut<Time<Virtual>>) {
+    info!("Countdown finished");
+    time.unpause();
+}
+
 /// Set to clean state after a session
 fn cleanup(mut state: ResMut<NextState<RunningState>>, mut clicks: EventReader<ClickEvent>) {
-    state.set(RunningState::Paused);
+    state.set(RunningState::Setup);
     clicks.clear();
 }
 
+/// Freeze the game clock at the end of a session, so the final time stays on screen
+fn stop_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
 /// Set to clean state after a session
-fn cleanup_session(mut clicks: EventReader<ClickEvent>) {
+fn cleanup_session(
+    mut clicks: EventReader<ClickEvent>,
+    mut time: ResMut<Time<Virtual>>,
+    mut game_over: ResMut<Events<GameOverEvent>>,
+) {
     clicks.clear();
+    // Events are only dropped after fixed updates, which did not run while the time was paused.
+    // A game over sent again in the frame before the session finished must not end the next one.
+    game_over.clear();
+    // The menus run on a normal clock, the next session pauses it again for the countdown
+    time.unpause();
+    time.set_relative_speed(1.0);
+}
+
+/// Draw thin lines on the boundaries between cells
+fn grid_lines(commands: &mut Commands, config: &GridConfig, color: Color) {
+    const WIDTH: f32 = 1.0;
+    let (cols, rows) = (config.cols as f32, config.rows as f32);
+    let vertical = (1..config.cols).map(|x| {
+        let center = config.cell_to_world(Vec2::new(x as f32, rows / 2.0));
+        (center, Vec2::new(WIDTH, FIELD_SIZE_Y))
+    });
+    let horizontal = (1..config.rows).map(|y| {
+        let center = config.cell_to_world(Vec2::new(cols / 2.0, y as f32));
+        (center, Vec2::new(FIELD_SIZE_X, WIDTH))
+    });
+    for (center, size) in vertical.chain(horizontal) {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform: Transform::from_translation(center.extend(0.5)),
+                ..default()
+            },
+            OnGameScreen,
+        ));
+    }
+}
+
+/// Draw a fading line between two cells to lead the eye towards a new tile
+fn trail(commands: &mut Commands, config: &GridConfig, from: UVec2, to: UVec2, color: Color) {
+    let start = config.cell_to_world(from.as_vec2() + 0.5);
+    let end = config.cell_to_world(to.as_vec2() + 0.5);
+    let direction = end - start;
+    let transform = Transform::from_translation(((start + end) / 2.0).extend(0.8))
+        .with_rotation(Quat::from_rotation_z(direction.y.atan2(direction.x)));
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: color.with_a(0.4),
+                custom_size: Some(Vec2::new(direction.length(), 6.0)),
+                ..default()
+            },
+            transform,
+            ..default()
+        },
+        Fading::new(0.3, 0.4),
+        OnSessionScreen,
+    ));
+}
+
+/// Scale in tiles with a [`SpawnAnim`].
+/// Clicks are mapped to cells, so a growing tile can already be clicked unless
+/// [`Settings::min_clickable_scale`] is set.
+fn animate_spawn(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut tiles: Query<(Entity, &mut SpawnAnim, &mut Transform)>,
+) {
+    for (entity, mut anim, mut transform) in &mut tiles {
+        anim.timer.tick(time.delta());
+        // Ease out, growing fast at first
+        let t = anim.timer.fraction();
+        let scale = 1.0 - (1.0 - t).powi(3);
+        transform.scale = Vec3::new(scale, scale, 1.0);
+        if anim.timer.finished() {
+            commands.entity(entity).remove::<SpawnAnim>();
+        }
+    }
+}
+
+/// Show a short text rising from the center of the tile at `pos`
+fn popup(
+    commands: &mut Commands,
+    config: &GridConfig,
+    font: Handle<Font>,
+    text: String,
+    pos: UVec2,
+    color: Color,
+) {
+    let center = config.cell_to_world(pos.as_vec2() + 0.5);
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                text,
+                TextStyle {
+                    font,
+                    font_size: 36.0,
+                    color,
+                },
+            ),
+            transform: Transform::from_translation(center.extend(4.0)),
+            ..default()
+        },
+        Popup {
+            timer: Timer::from_seconds(Popup::DURATION, TimerMode::Once),
+        },
+        OnSessionScreen,
+    ));
+}
+
+/// Move up, fade out and remove [`Popup`] texts
+fn animate_popups(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut popups: Query<(Entity, &mut Popup, &mut Transform, &mut Text)>,
+) {
+    for (entity, mut popup, mut transform, mut text) in &mut popups {
+        popup.timer.tick(time.delta());
+        if popup.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        transform.translation.y += Popup::RISE * time.delta_seconds();
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(popup.timer.fraction_remaining());
+        }
+    }
+}
+
+/// Shake the game camera when a misclick ended the session
+fn shake_on_misclick(
+    mut commands: Commands,
+    reason: Res<GameOverReason>,
+    cameras: Query<Entity, (With<Camera>, With<OnGameScreen>)>,
+) {
+    if *reason != GameOverReason::Misclick {
+        return;
+    }
+    for camera in &cameras {
+        commands.entity(camera).insert(Shake {
+            timer: Timer::from_seconds(Shake::DURATION, TimerMode::Once),
+            intensity: Shake::INTENSITY,
+        });
+    }
+}
+
+/// Offset cameras with [`Shake`] randomly and move them back once finished
+fn shake_camera(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut cameras: Query<(Entity, &mut Shake, &mut Transform)>,
+) {
+    for (entity, mut shake, mut transform) in &mut cameras {
+        shake.timer.tick(time.delta());
+        if shake.timer.finished() {
+            transform.translation.x = 0.0;
+            transform.translation.y = 0.0;
+            commands.entity(entity).remove::<Shake>();
+            continue;
+        }
+        let offset = shake.intensity * shake.timer.fraction_remaining();
+        transform.translation.x = (rand::random::<f32>() * 2.0 - 1.0) * offset;
+        transform.translation.y = (rand::random::<f32>() * 2.0 - 1.0) * offset;
+    }
+}
+
+/// Fade out and remove [`Fading`] sprites
+fn fade_sprites(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut sprites: Query<(Entity, &mut Fading, &mut Sprite)>,
+) {
+    for (entity, mut fading, mut sprite) in &mut sprites {
+        fading.timer.tick(time.delta());
+        if fading.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+        } else {
+            sprite
+                .color
+                .set_a(fading.alpha * fading.timer.fraction_remaining());
+        }
+    }
 }
 
 /// Create a new tile at `pos`
 /// `S` is a marker component for marking a tile as either [`OnGameScreen`] or [`OnSessionScreen`]
-fn tile<S: Default + Component>(commands: &mut Commands, pos: UVec3, color: Color) -> Entity {
-    let x = -(FIELD_SIZE_X - TILE_SIZE_X) / 2.0 + pos.x as f32 * TILE_SIZE_X;
-    let y = -(FIELD_SIZE_Y - TILE_SIZE_Y) / 2.0 + pos.y as f32 * TILE_SIZE_Y;
-    let y = -y - SCORE_HEIGHT / 2.0;
-    let translation = Vec3::new(x, y, pos.z as f32);
+fn tile<S: Default + Component>(
+    commands: &mut Commands,
+    config: &GridConfig,
+    pos: UVec3,
+    color: Color,
+) -> Entity {
+    let center = config.cell_to_world(pos.truncate().as_vec2() + 0.5);
+    let translation = center.extend(pos.z as f32);
     commands
         .spawn((
             SpriteBundle {
                 sprite: Sprite {
                     color,
-                    custom_size: Some(Vec2::new(TILE_SIZE_X, TILE_SIZE_Y) * 0.95),
+                    custom_size: Some(config.tile_size() * 0.95),
                     ..default()
                 },
                 transform: Transform::from_translation(translation),
@@ -311,154 +1507,1278 @@ fn tile<S: Default + Component>(commands: &mut Commands, pos: UVec3, color: Colo
         .id()
 }
 
-/// Send an event to spawn a new tile when the timer finishes
-fn tile_spawn_timer(timer: Res<SpawnTimer>, mut events: EventWriter<SpawnNewEvent>) {
+/// Pick a free cell for a new tile, close to the last spawned tile with
+/// [`SpawnPattern::Clustered`].
+/// Returns `None` when the grid is full.
+fn spawn_position(
+    tiles: &Grid,
+    last_spawn: UVec2,
+    pattern: SpawnPattern,
+    rng: &mut impl rand::Rng,
+) -> Option<UVec2> {
+    const SPAWN_DISTANCE: isize = 2;
+    if pattern == SpawnPattern::Uniform {
+        use rand::seq::IteratorRandom;
+        return (0..tiles.tiles.len())
+            .filter(|i| tiles.tiles[*i].is_none())
+            .choose(rng)
+            .map(|i| tiles.position(i));
+    }
+    // Increase spawn check radius when failing to find a new space.
+    for extra_range in 0.. {
+        if tiles.is_full() {
+            break;
+        }
+        let GridConfig { cols, rows } = tiles.config;
+        let x = rng.gen_range(0..cols);
+        let y = rng.gen_range(0..rows);
+        let dx = x as isize - last_spawn.x as isize;
+        let dy = y as isize - last_spawn.y as isize;
+        let dx = dx.abs().min(SPAWN_DISTANCE + extra_range / 2) * dx.signum();
+        let dy = dy.abs().min(SPAWN_DISTANCE + extra_range / 2) * dy.signum();
+        // Limit the distance of new spawned tiles from the last spawned tile
+        let x = (last_spawn.x as usize)
+            .saturating_add_signed(dx)
+            .min(cols - 1);
+        let y = (last_spawn.y as usize)
+            .saturating_add_signed(dy)
+            .min(rows - 1);
+        let pos = UVec2::new(x as u32, y as u32);
+        // Avoid the last position unless it is the only one left
+        if pos == last_spawn && tiles.free_tiles() > 1 {
+            continue;
+        }
+        if tiles.is_free(x, y) {
+            return Some(pos);
+        }
+    }
+    None
+}
+
+/// Advance the [`SpawnTimer`] with the fixed timestep, independent of the frame rate
+fn tick_spawn_timer(mut timer: ResMut<SpawnTimer>, time: Res<Time<Fixed>>) {
+    timer.0.tick(time.delta());
+}
+
+/// Send events to spawn new tiles when the timer finishes, unless [`SpawnPaused`]
+fn tile_spawn_timer(
+    timer: Res<SpawnTimer>,
+    multi_spawn: Res<MultiSpawn>,
+    time: Res<GameTime>,
+    paused: Res<SpawnPaused>,
+    mut events: EventWriter<SpawnNewEvent>,
+) {
+    if paused.0 {
+        return;
+    }
     if timer.0.finished() {
-        events.send(SpawnNewEvent::Normal);
+        let count = multi_spawn.count(time.0.elapsed_secs());
+        events.send_batch((0..count).map(|_| SpawnNewEvent::Normal));
+    }
+}
+
+/// With [`Settings::metronome`] tick and faintly flash the field near the end of each
+/// [`SpawnTimer`] period.
+/// The timer is reset when the last tile is cleared, which simply starts the next beat.
+fn metronome(
+    mut commands: Commands,
+    timer: Res<SpawnTimer>,
+    settings: Res<Settings>,
+    palette: Res<Palette>,
+    mut sound: EventWriter<SoundEvent>,
+    config: Res<GridConfig>,
+    mut previous: Local<f32>,
+) {
+    /// Fraction of the spawn period at which the beat is given
+    const BEAT: f32 = 0.8;
+    let fraction = timer.0.fraction();
+    let beat = *previous < BEAT && fraction >= BEAT;
+    *previous = fraction;
+    if !settings.metronome || !beat {
+        return;
     }
+    sound.send(SoundEvent::Tick);
+    let center = config.cell_to_world(config.size().as_vec2() / 2.0);
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: palette.accent,
+                custom_size: Some(Vec2::new(FIELD_SIZE_X, FIELD_SIZE_Y)),
+                ..default()
+            },
+            transform: Transform::from_translation(center.extend(3.0)),
+            ..default()
+        },
+        Fading::new(0.15, 0.15),
+        OnSessionScreen,
+    ));
 }
 
-/// Spawn the tiles received from the event reader
+/// Spawn the tiles received from the event reader.
+/// The timer and clearing the last tile can both request a tile in the same step,
+/// only [`EventLimits::spawns`] normal tiles are spawned per step so this does not
+/// result in a burst of tiles. No normal tiles are spawned while [`SpawnPaused`],
+/// error tiles are always shown.
+#[allow(clippy::too_many_arguments)]
 fn spawn_tile(
     mut commands: Commands,
-    mut tiles: ResMut<GameGrid>,
+    mut tiles: ResMut<Grid>,
     mut events: EventReader<SpawnNewEvent>,
     mut timer: ResMut<SpawnTimer>,
     mut last_spawn: ResMut<LastSpawn>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    time: Res<GameTime>,
+    mut sound: EventWriter<SoundEvent>,
+    limits: Res<EventLimits>,
+    special: Res<SpecialTiles>,
+    combo: Res<Combo>,
+    mut game_rng: ResMut<GameRng>,
+    multi_spawn: Res<MultiSpawn>,
+    (mode, difficulty): (Res<GameMode>, Res<Difficulty>),
+    paused: Res<SpawnPaused>,
 ) {
-    use rand::{thread_rng, Rng};
-    const SPAWN_DISTANCE: isize = 2;
-    for e in events.read().take(1) {
-        let mut rng = thread_rng();
-
+    use rand::Rng;
+    let rng = &mut game_rng.rng;
+    let max_spawns = limits.spawns * multi_spawn.count(time.0.elapsed_secs());
+    let mut spawned = 0;
+    for e in events.read() {
+        let e = match e {
+            SpawnNewEvent::Normal if rng.gen_bool(special.bonus_chance) => &SpawnNewEvent::Bonus,
+            SpawnNewEvent::Normal if mode.timed() && rng.gen_bool(special.time_bonus_chance) => {
+                &SpawnNewEvent::TimeBonus
+            }
+            e => e,
+        };
         match e {
-            SpawnNewEvent::Normal => {
-                let color = Color::rgb(0.1, 0.1, 0.1);
-                // Increase spawn check radius when failing to find a new space.
-                for extra_range in 0.. {
-                    if tiles.is_full() {
-                        break;
+            SpawnNewEvent::Normal | SpawnNewEvent::Bonus | SpawnNewEvent::TimeBonus
+                if spawned >= max_spawns || paused.0 => {}
+            SpawnNewEvent::Normal | SpawnNewEvent::Bonus | SpawnNewEvent::TimeBonus => {
+                spawned += 1;
+                if let Some(pos) = spawn_position(&tiles, last_spawn.0, settings.spawn_pattern, rng)
+                {
+                    let shuffle = combo.count >= special.shuffle_combo;
+                    let kind = if matches!(e, SpawnNewEvent::Bonus) {
+                        TileKind::Bonus
+                    } else if matches!(e, SpawnNewEvent::TimeBonus) {
+                        TileKind::TimeBonus
+                    } else if shuffle && rng.gen_bool(special.shuffle_chance) {
+                        TileKind::Shuffle
+                    } else if difficulty.moving_tiles() && rng.gen_bool(special.moving_chance) {
+                        TileKind::Moving
+                    } else {
+                        TileKind::Normal
+                    };
+                    let color = match kind {
+                        TileKind::Normal | TileKind::Moving if settings.value_gradient => {
+                            palette.tile_gradient(1.0)
+                        }
+                        kind => kind.color(&palette),
+                    };
+                    let config = &tiles.config;
+                    let entity =
+                        tile::<OnSessionScreen>(&mut commands, config, pos.extend(1), color);
+                    commands
+                        .entity(entity)
+                        .insert((SpawnedAt(time.0.elapsed()), SpawnAnim::new()));
+                    if kind == TileKind::Moving {
+                        commands.entity(entity).insert(Moving::new());
                     }
-                    let x = rng.gen_range(0..TILE_NUM_X);
-                    let y = rng.gen_range(0..TILE_NUM_Y);
-                    let dx = x as isize - last_spawn.0.x as isize;
-                    let dy = y as isize - last_spawn.0.y as isize;
-                    let dx = dx.abs().min(SPAWN_DISTANCE + extra_range / 2) * dx.signum();
-                    let dy = dy.abs().min(SPAWN_DISTANCE + extra_range / 2) * dy.signum();
-                    // Limit the distance of new spawned tiles from the last spawned tile
-                    let x = (last_spawn.0.x as usize)
-                        .saturating_add_signed(dx)
-                        .min(TILE_NUM_X - 1);
-                    let y = (last_spawn.0.y as usize)
-                        .saturating_add_signed(dy)
-                        .min(TILE_NUM_Y - 1);
-                    let pos = UVec2::new(x as u32, y as u32);
-                    if pos == last_spawn.0 {
-                        continue;
+                    if settings.spawn_trail {
+                        trail(&mut commands, config, last_spawn.0, pos, palette.tile);
                     }
-                    if tiles.is_free(x, y) {
-                        let entity = tile::<OnSessionScreen>(&mut commands, pos.extend(1), color);
-                        last_spawn.0 = pos;
-                        tiles.set(x, y, entity);
-                        timer.0.reset();
-                        break;
+                    last_spawn.0 = pos;
+                    tiles.set(pos.x as usize, pos.y as usize, entity, kind);
+                    timer.0.reset();
+                    if settings.spawn_sound {
+                        sound.send(SoundEvent::Spawn);
                     }
                 }
             }
             SpawnNewEvent::Error((x, y)) => {
-                let color = Color::rgb(0.9, 0.1, 0.1);
-                tile::<OnSessionScreen>(&mut commands, UVec3::new(*x, *y, 2), color);
+                let pos = UVec3::new(*x, *y, 2);
+                tile::<OnSessionScreen>(&mut commands, &tiles.config, pos, palette.error);
+            }
+            SpawnNewEvent::Flash((x, y)) => {
+                let pos = UVec3::new(*x, *y, 2);
+                let entity =
+                    tile::<OnSessionScreen>(&mut commands, &tiles.config, pos, palette.error);
+                commands.entity(entity).insert(Fading::new(0.3, 1.0));
             }
+            // Handled by shuffle_tiles
+            SpawnNewEvent::Shuffle => {}
         }
     }
     events.clear();
 }
 
-/// Update the timer of the tiles on the grid to reduce points.
-/// Using [`Time<Virtual>`] makes the tiles lose points faster as the game progresses.
-fn update_tile_points(time: Res<Time<Virtual>>, mut tiles: ResMut<GameGrid>) {
-    tiles.tick(time.delta());
+/// Move all tiles to new random cells on [`SpawnNewEvent::Shuffle`]
+fn shuffle_tiles(
+    mut events: EventReader<SpawnNewEvent>,
+    mut tiles: ResMut<Grid>,
+    mut transforms: Query<&mut Transform>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    if !events.read().any(|e| matches!(e, SpawnNewEvent::Shuffle)) {
+        return;
+    }
+    for (entity, pos) in tiles.shuffle(&mut game_rng.rng) {
+        if let Ok(mut transform) = transforms.get_mut(entity) {
+            let center = tiles.config.cell_to_world(pos.as_vec2() + 0.5);
+            transform.translation = center.extend(transform.translation.z);
+        }
+    }
+}
+
+/// Let [`TileKind::Moving`] tiles hop to a random free neighbouring cell.
+/// Tiles without points left stay where they are, as do tiles without a free neighbour.
+fn move_tiles(
+    time: Res<Time<Fixed>>,
+    mut tiles: ResMut<Grid>,
+    mut moving: Query<(Entity, &mut Moving, &mut Transform)>,
+    mut game_rng: ResMut<GameRng>,
+) {
+    use rand::seq::SliceRandom;
+    for (entity, mut moving, mut transform) in &mut moving {
+        if !moving.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let Some(from) = tiles.position_of_scoring(entity) else {
+            continue;
+        };
+        let Some(&to) = tiles.free_neighbours(from).choose(&mut game_rng.rng) else {
+            continue;
+        };
+        tiles.move_tile(from, to);
+        let center = tiles.config.cell_to_world(to.as_vec2() + 0.5);
+        transform.translation = center.extend(transform.translation.z);
+    }
+}
+
+/// Update the timer of the tiles on the grid to reduce points and remove tiles at the end of
+/// their [`TileTiming::lifetime_secs`].
+/// [`Time<Fixed>`] follows [`Time<Virtual>`], which makes the tiles lose points faster as the
+/// game progresses.
+fn update_tile_points(
+    mut commands: Commands,
+    time: Res<Time<Fixed>>,
+    mut tiles: ResMut<Grid>,
+    mut expired: EventWriter<TileExpiredEvent>,
+    mut new_tile: EventWriter<SpawnNewEvent>,
+) {
+    let (expired_tiles, vanished) = tiles.tick(time.delta());
+    for (pos, entity) in expired_tiles {
+        expired.send(TileExpiredEvent {
+            x: pos.x as usize,
+            y: pos.y as usize,
+            entity,
+        });
+    }
+    if vanished.is_empty() {
+        return;
+    }
+    for entity in vanished {
+        commands.entity(entity).despawn_recursive();
+    }
+    if tiles.filled_tiles() == 0 {
+        new_tile.send(SpawnNewEvent::Normal);
+    }
 }
 
-/// Handle click events
+/// Move the [`TrainerHint`] behind the tile closest to losing all of its points
+fn update_trainer_hint(
+    tiles: Res<Grid>,
+    mut hints: Query<(&mut Transform, &mut Visibility), With<TrainerHint>>,
+) {
+    for (mut transform, mut visibility) in &mut hints {
+        let Some((pos, _)) = tiles.most_urgent() else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        // Between the background and the tiles
+        let center = tiles.config.cell_to_world(pos.as_vec2() + 0.5);
+        transform.translation = center.extend(0.5);
+        *visibility = Visibility::Visible;
+    }
+}
+
+/// Show the current points of each tile as a number on it, see [`Settings::tile_values`]
+fn update_tile_values(
+    mut commands: Commands,
+    tiles: Res<Grid>,
+    assets: Res<Assets>,
+    settings: Res<Settings>,
+    children: Query<&Children>,
+    mut texts: Query<&mut Text, With<TileValue>>,
+) {
+    if !settings.tile_values {
+        return;
+    }
+    for (entity, points) in tiles.values() {
+        let label = children
+            .get(entity)
+            .into_iter()
+            .flatten()
+            .find(|child| texts.contains(**child));
+        match label {
+            Some(label) => {
+                if let Ok(mut text) = texts.get_mut(*label) {
+                    text.sections[0].value = points.to_string();
+                }
+            }
+            None => {
+                let text = Text::from_section(
+                    points.to_string(),
+                    TextStyle {
+                        font: assets.font.clone(),
+                        font_size: 32.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                    },
+                );
+                let label = commands
+                    .spawn((
+                        Text2dBundle {
+                            text,
+                            transform: Transform::from_xyz(0.0, 0.0, 0.1),
+                            ..default()
+                        },
+                        TileValue,
+                    ))
+                    .id();
+                commands.entity(entity).add_child(label);
+            }
+        }
+    }
+}
+
+/// Blend the color of normal tiles with their remaining time, see [`Settings::value_gradient`]
+fn update_tile_colors(
+    tiles: Res<Grid>,
+    settings: Res<Settings>,
+    palette: Res<Palette>,
+    mut sprites: Query<&mut Sprite>,
+) {
+    if !settings.value_gradient {
+        return;
+    }
+    let normal = tiles
+        .tiles
+        .iter()
+        .flatten()
+        .filter(|(_, _, _, kind)| matches!(kind, TileKind::Normal | TileKind::Moving));
+    for (entity, timer, _, _) in normal {
+        if let Ok(mut sprite) = sprites.get_mut(*entity) {
+            sprite.color = palette.tile_gradient(timer.fraction_remaining());
+        }
+    }
+}
+
+/// Shrink the [`DecayOverlay`] of each tile with its remaining time
+fn update_decay_overlays(
+    mut commands: Commands,
+    tiles: Res<Grid>,
+    settings: Res<Settings>,
+    children: Query<&Children>,
+    mut overlays: Query<&mut Transform, With<DecayOverlay>>,
+) {
+    if !settings.decay_overlay {
+        return;
+    }
+    for (entity, fraction) in tiles.fractions() {
+        let overlay = children
+            .get(entity)
+            .into_iter()
+            .flatten()
+            .find(|child| overlays.contains(**child));
+        let scale = Vec3::new(fraction, fraction, 1.0);
+        match overlay {
+            Some(overlay) => {
+                if let Ok(mut transform) = overlays.get_mut(*overlay) {
+                    transform.scale = scale;
+                }
+            }
+            None => {
+                let overlay = commands
+                    .spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color: Color::rgba(1.0, 1.0, 1.0, 0.25),
+                                custom_size: Some(tiles.config.tile_size() * 0.95),
+                                ..default()
+                            },
+                            transform: Transform::from_xyz(0.0, 0.0, 0.05).with_scale(scale),
+                            ..default()
+                        },
+                        DecayOverlay,
+                    ))
+                    .id();
+                commands.entity(entity).add_child(overlay);
+            }
+        }
+    }
+}
+
+/// With [`Difficulty::fatal_expiry`] flash the tile closest to expiring during its last second
+fn flash_endangered_tile(
+    tiles: Res<Grid>,
+    difficulty: Res<Difficulty>,
+    palette: Res<Palette>,
+    time: Res<Time<Real>>,
+    mut sprites: Query<&mut Sprite>,
+    mut flashing: Local<Option<(Entity, TileKind)>>,
+    mode: Res<GameMode>,
+) {
+    if !difficulty.fatal_expiry() || *mode == GameMode::Zen {
+        return;
+    }
+    let urgent = tiles
+        .most_urgent()
+        .filter(|(_, (_, timer, _, _))| timer.remaining_secs() < 1.0)
+        .map(|(_, (entity, _, _, kind))| (*entity, *kind));
+    // Restore the previous tile, it could have been shuffled or replaced by a more urgent one
+    if let Some((entity, kind)) = flashing.take() {
+        if urgent.map(|(e, _)| e) != Some(entity) {
+            if let Ok(mut sprite) = sprites.get_mut(entity) {
+                sprite.color = kind.color(&palette);
+            }
+        }
+    }
+    let Some((entity, kind)) = urgent else {
+        return;
+    };
+    if let Ok(mut sprite) = sprites.get_mut(entity) {
+        let on = (time.elapsed_seconds() * 8.0).sin() > 0.0;
+        sprite.color = if on {
+            palette.error
+        } else {
+            kind.color(&palette)
+        };
+        *flashing = Some((entity, kind));
+    }
+}
+
+/// Apply the [`TileExpiry`] rules to tiles which are no longer worth any points
+#[allow(clippy::too_many_arguments)]
+fn expire_tiles(
+    mut commands: Commands,
+    mut events: EventReader<TileExpiredEvent>,
+    rules: Res<TileExpiry>,
+    mut tiles: ResMut<Grid>,
+    mut score: ResMut<Score>,
+    mut new_tile: EventWriter<SpawnNewEvent>,
+    mut game_over: EventWriter<GameOverEvent>,
+    difficulty: Res<Difficulty>,
+    mut combo: ResMut<Combo>,
+    mut audit: ResMut<ScoreAudit>,
+    time: Res<GameTime>,
+    (mut stats, mode): (ResMut<SessionStats>, Res<GameMode>),
+) {
+    for event in events.read() {
+        stats.tiles_expired += 1;
+        if rules.penalty > 0 {
+            let before = score.0;
+            score.0 = score.0.saturating_sub(rules.penalty);
+            audit.record(ScoreEntry {
+                time: time.0.elapsed(),
+                source: ScoreSource::ExpiryPenalty,
+                base: rules.penalty,
+                speed: 1.0,
+                combo: 1.0,
+                before,
+                after: score.0,
+            });
+        }
+        combo.reset();
+        if difficulty.fatal_expiry() && *mode != GameMode::Zen {
+            game_over.send(GameOverEvent(GameOverReason::TileExpired));
+        }
+        // The tile could have been clicked or vanished in the mean time,
+        // with a lifetime the tile is only removed at the end of it
+        if !rules.despawn
+            || tiles.timing.lifetime_secs.is_some()
+            || tiles.get(event.x, event.y) != Some(event.entity)
+        {
+            continue;
+        }
+        tiles.take(event.x, event.y);
+        commands.entity(event.entity).despawn_recursive();
+        if tiles.filled_tiles() == 0 {
+            new_tile.send(SpawnNewEvent::Normal);
+        }
+    }
+}
+
+/// Configuration consulted when handling clicks
+#[derive(SystemParam)]
+struct ClickRules<'w> {
+    mode: Res<'w, GameMode>,
+    target: Res<'w, TargetScore>,
+    limits: Res<'w, EventLimits>,
+    settings: Res<'w, Settings>,
+    speed_scaled: Res<'w, SpeedScaledScoring>,
+    warmup: Res<'w, WarmupForgiveness>,
+    logging: Res<'w, ClickLogging>,
+    special: Res<'w, SpecialTiles>,
+}
+
+/// Events sent and popups shown when handling clicks
+#[derive(SystemParam)]
+struct ClickEffects<'w> {
+    new_tile: EventWriter<'w, SpawnNewEvent>,
+    sound: EventWriter<'w, SoundEvent>,
+    game_over: EventWriter<'w, GameOverEvent>,
+    assets: Res<'w, Assets>,
+    palette: Res<'w, Palette>,
+}
+
+/// Debugging records written when handling clicks
+#[derive(SystemParam)]
+struct ClickJournals<'w> {
+    clicks: ResMut<'w, ClickLog>,
+    score: ResMut<'w, ScoreAudit>,
+    lives: ResMut<'w, Lives>,
+    replay: ResMut<'w, Replay>,
+    ticks: Res<'w, SessionTicks>,
+    intervals: ResMut<'w, ClickIntervals>,
+    bounds: Res<'w, ReactionBounds>,
+}
+
+/// Handle click events.
+/// At most [`EventLimits::clicks`] clicks are handled per step and handling stops at the first
+/// misclick, so a burst of buffered input can't clear a row of tiles or end the game twice.
+#[allow(clippy::too_many_arguments)]
 fn click(
     mut commands: Commands,
     mut clicks: EventReader<ClickEvent>,
-    mut tiles: ResMut<GameGrid>,
+    mut tiles: ResMut<Grid>,
     mut score: ResMut<Score>,
-    mut new_tile: EventWriter<SpawnNewEvent>,
-    mut sound: EventWriter<SoundEvent>,
-    mut state: ResMut<NextState<RunningState>>,
+    mut reaction_times: ResMut<ReactionTimes>,
+    spawned_at: Query<&SpawnedAt>,
+    time: Res<GameTime>,
+    transforms: Query<&Transform>,
+    virtual_time: Res<Time<Virtual>>,
+    mut combo: ResMut<Combo>,
+    mut stats: ResMut<SessionStats>,
+    mut extra_time: ResMut<ExtraTime>,
+    effects: ClickEffects,
+    journals: ClickJournals,
+    rules: ClickRules,
 ) {
-    for event in clicks.read() {
-        let x = event.tile_x.min(TILE_NUM_X - 1);
-        let y = event.tile_y.min(TILE_NUM_Y - 1);
-        if let Some((entity, s)) = tiles.take(x, y) {
-            commands.entity(entity).despawn_recursive();
-            score.0 += s;
+    let ClickRules {
+        mode,
+        target,
+        limits,
+        settings,
+        speed_scaled,
+        warmup,
+        logging,
+        special,
+    } = rules;
+    let ClickEffects {
+        mut new_tile,
+        mut sound,
+        mut game_over,
+        assets,
+        palette,
+    } = effects;
+    let ClickJournals {
+        clicks: mut log,
+        score: mut audit,
+        mut lives,
+        mut replay,
+        ticks,
+        mut intervals,
+        bounds,
+    } = journals;
+    let now = time.0.elapsed();
+    let age = |entity| spawned_at.get(entity).ok().map(|s| now.saturating_sub(s.0));
+    for event in clicks.read().take(limits.clicks) {
+        replay.record(ticks.0, event);
+        let x = event.tile_x.min(tiles.config.cols - 1);
+        let y = event.tile_y.min(tiles.config.rows - 1);
+        let mut record = |outcome, tile_age| {
+            let record = ClickRecord {
+                time: now,
+                x,
+                y,
+                outcome,
+                tile_age,
+            };
+            log.record(&logging, record);
+        };
+        // Ignore tiles which are still too small to see, e.g. during a spawn animation
+        let armed = |entity| {
+            transforms
+                .get(entity)
+                .map_or(true, |t| t.scale.x >= settings.min_clickable_scale)
+        };
+        if let Some(entity) = tiles.get(x, y).filter(|entity| !armed(*entity)) {
+            record(ClickOutcome::Ignored, age(entity));
+            continue;
+        }
+        if let Some((entity, s, kind)) = tiles.take(x, y) {
+            record(ClickOutcome::Hit, age(entity));
+            intervals.record(now, bounds.min);
+            combo.hit(now);
+            stats.hits += 1;
+            stats.max_combo = stats.max_combo.max(combo.count);
+            if let Some(reaction_time) = age(entity) {
+                reaction_times.0.push(reaction_time);
+            }
+            // The cell is free right away, the tile only stays visible as a short flash
+            commands.entity(entity).despawn_descendants().insert((
+                Sprite {
+                    color: palette.hit,
+                    custom_size: Some(tiles.config.tile_size() * 0.95),
+                    ..default()
+                },
+                Fading::new(HIT_FLASH, 1.0),
+            ));
+            let speed = if speed_scaled.0 {
+                virtual_time.relative_speed()
+            } else {
+                1.0
+            };
+            let bonus = if kind == TileKind::Bonus { 2.0 } else { 1.0 };
+            let multiplier = combo.multiplier() * bonus;
+            let before = score.0;
+            score.0 += (s as f32 * speed * multiplier).round() as usize;
+            audit.record(ScoreEntry {
+                time: now,
+                source: ScoreSource::Tile,
+                base: s,
+                speed,
+                combo: multiplier,
+                before,
+                after: score.0,
+            });
             if tiles.filled_tiles() == 0 {
                 new_tile.send(SpawnNewEvent::Normal);
             }
-            sound.send(SoundEvent::Normal);
+            if kind == TileKind::Shuffle {
+                new_tile.send(SpawnNewEvent::Shuffle);
+            }
+            // Fresher tiles give brighter popups
+            let brightness = 0.5 + 0.5 * s as f32 / tiles.max_points() as f32;
+            let mut text = format!("+{}", score.0 - before);
+            let mut color = Color::rgb(brightness, brightness, brightness);
+            match kind {
+                TileKind::Bonus => {
+                    color = palette.bonus;
+                    sound.send(SoundEvent::Bonus)
+                }
+                TileKind::TimeBonus => {
+                    let added = extra_time.add(special.time_bonus, special.max_time_bonus);
+                    if added > Duration::ZERO {
+                        text.push_str(&format!(" +{}s", added.as_secs()));
+                        color = palette.time_bonus;
+                    }
+                    sound.send(SoundEvent::TimeBonus)
+                }
+                TileKind::Normal | TileKind::Shuffle | TileKind::Moving => {
+                    sound.send(SoundEvent::Normal)
+                }
+            };
+            let pos = UVec2::new(x as u32, y as u32);
+            popup(
+                &mut commands,
+                &tiles.config,
+                assets.font.clone(),
+                text,
+                pos,
+                color,
+            );
+            if *mode == GameMode::Target && score.0 >= target.0 {
+                game_over.send(GameOverEvent(GameOverReason::TargetReached));
+            }
+        } else if time.0.elapsed_secs() < warmup.0 || mode.open_ended() {
+            // Forgive misclicks while the player is still orienting, practicing or relaxing
+            record(ClickOutcome::Forgiven, None);
+            combo.reset();
+            stats.misses += 1;
+            new_tile.send(SpawnNewEvent::Flash((x as u32, y as u32)));
+            sound.send(SoundEvent::Error);
         } else {
-            new_tile.send(SpawnNewEvent::Error((x as u32, y as u32)));
-            state.set(RunningState::Finished);
+            record(ClickOutcome::Miss, None);
+            combo.reset();
+            stats.misses += 1;
             sound.send(SoundEvent::Error);
+            lives.0 = lives.0.saturating_sub(1);
+            if lives.0 > 0 {
+                new_tile.send(SpawnNewEvent::Flash((x as u32, y as u32)));
+                continue;
+            }
+            new_tile.send(SpawnNewEvent::Error((x as u32, y as u32)));
+            game_over.send(GameOverEvent(GameOverReason::Misclick));
+            break;
         }
     }
+    // Drop clicks exceeding the limit, e.g. buffered during a lag spike
+    clicks.clear();
 }
 
 /// Update score and time text in UI
-fn update_score(mut q: Query<&mut Text, With<ScoreText>>, score: Res<Score>, time: Res<GameTime>) {
+#[allow(clippy::too_many_arguments)]
+fn update_score(
+    mut q: Query<&mut Text, With<ScoreText>>,
+    score: Res<Score>,
+    time: Res<GameTime>,
+    virtual_time: Res<Time<Virtual>>,
+    mode: Res<GameMode>,
+    combo: Res<Combo>,
+    extra_time: Res<ExtraTime>,
+    palette: Res<Palette>,
+    lives: Res<Lives>,
+    difficulty: Res<Difficulty>,
+) {
     use std::fmt::Write;
-    let Some(mut text) = q.iter_mut().next() else {
-        return;
+    let counting_up = mode.counts_up();
+    let shown_time = if counting_up {
+        time.0.elapsed_secs()
+    } else {
+        (extra_time.duration() - time.0.elapsed_secs()).max(0.0)
     };
-    text.sections[1].value.clear();
-    write!(&mut text.sections[1].value, "{}", score.0).unwrap();
-    text.sections[3].value.clear();
-    write!(
-        &mut text.sections[3].value,
-        "{:.1}",
-        (GAME_DURATION - time.0.elapsed_secs()).max(0.0)
-    )
-    .unwrap();
-}
-
-/// Play a sound
-fn play_sound(mut commands: Commands, assets: Res<Assets>, mut events: EventReader<SoundEvent>) {
+    let time_color = if !counting_up && shown_time <= END_WARNING {
+        palette.error
+    } else {
+        Color::WHITE
+    };
+    // The text can have an outline copy which is updated the same way
+    for mut text in q.iter_mut() {
+        text.sections[1].value.clear();
+        write!(&mut text.sections[1].value, "{}", score.0).unwrap();
+        text.sections[3].value.clear();
+        write!(&mut text.sections[3].value, "{:.1}", shown_time).unwrap();
+        // Keep the color of the outline copy
+        if text.sections[0].style.color == Color::WHITE {
+            text.sections[3].style.color = time_color;
+        }
+        text.sections[4].value.clear();
+        let count = combo.current(time.0.elapsed());
+        if count > 0 {
+            write!(&mut text.sections[4].value, " Combo: {}", count).unwrap();
+        }
+        text.sections[5].value = lives_label(lives.0, *difficulty);
+        // Effective multiplier with SpeedScaledScoring
+        if let Some(section) = text.sections.get_mut(6) {
+            section.value.clear();
+            write!(&mut section.value, " x{:.1}", virtual_time.relative_speed()).unwrap();
+        }
+    }
+}
+
+/// Remaining [`Lives`] in the score UI, hidden when a single misclick ends the session anyway
+fn lives_label(lives: usize, difficulty: Difficulty) -> String {
+    if difficulty.lives() > 1 {
+        format!(" Lives: {}", lives)
+    } else {
+        String::new()
+    }
+}
+
+/// Play a sound with the [`Settings::volume`], unless [`Settings::muted`].
+/// Hits cycle through the hit samples with a slightly varied pitch, so fast runs do not
+/// repeat the exact same sound, and rise in pitch with the [`Combo`].
+fn play_sound(
+    mut commands: Commands,
+    assets: Res<Assets>,
+    mut events: EventReader<SoundEvent>,
+    time: Res<Time<Virtual>>,
+    settings: Res<Settings>,
+    combo: Res<Combo>,
+    mut next_hit: Local<usize>,
+) {
+    /// Largest relative change of the pitch of a hit
+    const HIT_PITCH_VARIATION: f32 = 0.06;
+    use bevy::audio::Volume;
+    if settings.muted {
+        events.clear();
+        return;
+    }
+    let volume = |v: f32| Volume::new(v * settings.volume);
     for sound in events.read() {
-        let audio = match sound {
-            SoundEvent::Normal => assets.hit.clone(),
-            SoundEvent::Error => assets.error.clone(),
+        let (audio, playback) = match sound {
+            SoundEvent::Normal => {
+                let Some(sample) = assets.hit.get(*next_hit % assets.hit.len().max(1)) else {
+                    continue;
+                };
+                *next_hit = next_hit.wrapping_add(1);
+                // Not taken from the GameRng, which would change the tiles of seeded sessions
+                let variation = 1.0 + HIT_PITCH_VARIATION * (2.0 * rand::random::<f32>() - 1.0);
+                let pitch = combo.pitch() * variation;
+                (
+                    sample.clone(),
+                    PlaybackSettings::DESPAWN
+                        .with_speed(pitch)
+                        .with_volume(volume(1.0)),
+                )
+            }
+            SoundEvent::Error => (
+                assets.error.clone(),
+                PlaybackSettings::DESPAWN.with_volume(volume(1.0)),
+            ),
+            SoundEvent::Spawn => (
+                assets.spawn.clone(),
+                PlaybackSettings::DESPAWN
+                    .with_speed(time.relative_speed())
+                    .with_volume(volume(0.4)),
+            ),
+            SoundEvent::Tick => (
+                assets.tick.clone(),
+                PlaybackSettings::DESPAWN.with_volume(volume(0.3)),
+            ),
+            SoundEvent::Bonus => (
+                assets.bonus.clone(),
+                PlaybackSettings::DESPAWN.with_volume(volume(1.0)),
+            ),
+            SoundEvent::TimeBonus => (
+                assets.time_bonus.clone(),
+                PlaybackSettings::DESPAWN.with_volume(volume(1.0)),
+            ),
+            SoundEvent::Warning => (
+                assets.warning.clone(),
+                PlaybackSettings::DESPAWN.with_volume(volume(0.8)),
+            ),
         };
         commands.spawn(AudioBundle {
             source: audio,
-            settings: PlaybackSettings::DESPAWN,
+            settings: playback,
         });
     }
 }
 
-/// Update timers and stopwatches and modify virtual time relative speed.
-/// The virtual time relative speed increases as the game progresses.
-/// This is a linear course over the duration of the game session.
+/// Advance the session stopwatch by one fixed step and modify virtual time relative speed.
+/// The virtual time relative speed increases as the game progresses,
+/// following the [`SpeedCurve`] of the [`Difficulty`] and the score as set by the [`Ramp`],
+/// eased by [`SpeedSmoothing`].
+#[allow(clippy::too_many_arguments)]
 fn update_game_time(
     mut stopwatch: ResMut<GameTime>,
-    mut spawn_time: ResMut<SpawnTimer>,
-    real_time: Res<Time<Real>>,
+    fixed: Res<Time<Fixed>>,
     mut time: ResMut<Time<Virtual>>,
-    mut state: ResMut<NextState<RunningState>>,
+    mut game_over: EventWriter<GameOverEvent>,
+    curve: Res<SpeedCurve>,
+    smoothing: Res<SpeedSmoothing>,
+    (mode, endless): (Res<GameMode>, Res<Endless>),
+    extra_time: Res<ExtraTime>,
+    (mut warned, mut sound): (ResMut<EndWarned>, EventWriter<SoundEvent>),
+    (ramp, score): (Res<Ramp>, Res<Score>),
 ) {
-    stopwatch.0.tick(real_time.delta());
-    spawn_time.0.tick(time.delta());
+    let step = fixed.delta().div_f32(time.relative_speed());
+    stopwatch.0.tick(step);
+    if *mode == GameMode::Zen {
+        // No ramp and no time limit
+        time.set_relative_speed(1.0);
+        return;
+    }
     let elapsed = stopwatch.0.elapsed_secs();
-    // t_r(t) = a t² + b
-    // t_r(0) = 1 => b = 1
-    // t_r(max) = 3 => a = (3-1)/max²
-    let relative_speed = (2.0 / GAME_DURATION.powi(2)) * elapsed.powi(2) + 1.0;
-    time.set_relative_speed(relative_speed);
-    if stopwatch.0.elapsed_secs() > GAME_DURATION {
-        info!("Time {} elapsed, finished", stopwatch.0.elapsed_secs());
+    let speed = smoothing.apply(
+        time.relative_speed(),
+        ramp.relative_speed(&curve, elapsed, score.0),
+        step.as_secs_f32(),
+    );
+    time.set_relative_speed(speed);
+    if *mode == GameMode::Endless {
+        if endless
+            .max_duration
+            .is_some_and(|max| stopwatch.0.elapsed() >= max)
+        {
+            info!("Endless session capped after {:?}", stopwatch.0.elapsed());
+            game_over.send(GameOverEvent(GameOverReason::Completed));
+        }
+    } else if mode.timed() {
+        let remaining = extra_time.duration() - stopwatch.0.elapsed_secs();
+        if remaining < 0.0 {
+            info!("Time {} elapsed, finished", stopwatch.0.elapsed_secs());
+            game_over.send(GameOverEvent(GameOverReason::TimeUp));
+        } else if remaining <= END_WARNING && !warned.0 {
+            warned.0 = true;
+            sound.send(SoundEvent::Warning);
+        }
+    }
+}
+
+/// End a [`GameMode::Practice`] or [`GameMode::Zen`] session when pressing Q
+fn stop_practice(
+    keys: Res<ButtonInput<KeyCode>>,
+    mode: Res<GameMode>,
+    mut game_over: EventWriter<GameOverEvent>,
+) {
+    if mode.open_ended() && keys.just_pressed(KeyCode::KeyQ) {
+        game_over.send(GameOverEvent(GameOverReason::Stopped));
+    }
+}
+
+/// Pause or resume the session when pressing a key bound to [`Action::Pause`]
+fn toggle_pause(
+    keys: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    state: Res<State<RunningState>>,
+    mut next_state: ResMut<NextState<RunningState>>,
+) {
+    if !bindings.just_pressed(&keys, Action::Pause) {
+        return;
+    }
+    match state.get() {
+        RunningState::Running => next_state.set(RunningState::Paused),
+        RunningState::Paused => next_state.set(RunningState::Running),
+        _ => {}
+    }
+}
+
+/// Pause the session when the window loses focus, e.g. switching tabs in the browser.
+/// [`GameTime`] only advances while running, so no session time is lost.
+fn pause_on_focus_loss(
+    mut events: EventReader<bevy::window::WindowFocused>,
+    mut next_state: ResMut<NextState<RunningState>>,
+) {
+    if events.read().any(|event| !event.focused) {
+        info!("Window lost focus");
+        next_state.set(RunningState::Paused);
+    }
+}
+
+/// Resume the session with a click, which requires the window to be focused again
+fn resume_on_click(
+    mouse_btn: Res<ButtonInput<MouseButton>>,
+    mut next_state: ResMut<NextState<RunningState>>,
+) {
+    if mouse_btn.just_pressed(MouseButton::Left) {
+        next_state.set(RunningState::Running);
+    }
+}
+
+/// Stop the virtual time, which halts spawning and decay
+fn pause(mut time: ResMut<Time<Virtual>>) {
+    info!("Paused");
+    time.pause();
+}
+
+/// Continue the virtual time with the relative speed matching the elapsed session time and score
+fn resume(
+    mut time: ResMut<Time<Virtual>>,
+    stopwatch: Res<GameTime>,
+    curve: Res<SpeedCurve>,
+    ramp: Res<Ramp>,
+    score: Res<Score>,
+    mode: Res<GameMode>,
+) {
+    info!("Resumed");
+    time.unpause();
+    if *mode != GameMode::Zen {
+        time.set_relative_speed(ramp.relative_speed(&curve, stopwatch.0.elapsed_secs(), score.0));
+    }
+}
+
+/// Keep the best score and store it when it was beaten, [`GameMode::Zen`] sessions don't count
+fn record_high_score(
+    score: Res<Score>,
+    mut high_score: ResMut<HighScore>,
+    mode: Res<GameMode>,
+    storage: Res<Storage>,
+) {
+    if score.0 > high_score.0 && *mode != GameMode::Zen {
+        info!("New high score {}", score.0);
+        high_score.0 = score.0;
+        storage.save(HighScore::KEY, &*high_score);
+    }
+}
+
+/// End the session on the first [`GameOverEvent`]
+fn game_over(
+    mut commands: Commands,
+    mut events: EventReader<GameOverEvent>,
+    mut state: ResMut<NextState<RunningState>>,
+) {
+    if let Some(GameOverEvent(reason)) = events.read().next() {
+        info!("Game over: {:?}", reason);
+        commands.insert_resource(*reason);
         state.set(RunningState::Finished);
     }
+    events.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Headless app with a session of `difficulty` which just started running
+    fn running_app(difficulty: Difficulty) -> App {
+        let mut app = headless_app(difficulty);
+        start_session(&mut app, RunningState::Running, None);
+        app
+    }
+
+    #[test]
+    fn endless_speed_is_capped() {
+        let mut app = headless_app(Difficulty::Hard);
+        app.insert_resource(GameMode::Endless);
+        start_session(&mut app, RunningState::Running, None);
+        assert_eq!(
+            app.world.resource::<SpeedCurve>().max_speed,
+            Endless::MAX_SPEED
+        );
+    }
+
+    #[test]
+    fn practice_survives_misclick() {
+        let mut app = headless_app(Difficulty::Normal);
+        app.insert_resource(GameMode::Practice);
+        start_session(&mut app, RunningState::Running, None);
+        // Past the warmup which forgives misclicks in every mode
+        for _ in 0..600 {
+            app.update();
+        }
+        let grid = app.world.resource::<Grid>();
+        let (cols, rows) = (grid.config.cols, grid.config.rows);
+        let (tile_x, tile_y) = (0..rows)
+            .flat_map(|y| (0..cols).map(move |x| (x, y)))
+            .find(|&(x, y)| grid.is_free(x, y))
+            .unwrap();
+        app.world.send_event(ClickEvent { tile_x, tile_y });
+        app.update();
+        app.update();
+        assert_eq!(
+            *app.world.resource::<State<RunningState>>().get(),
+            RunningState::Running
+        );
+    }
+
+    #[test]
+    fn restarted_session_runs_at_normal_speed() {
+        let mut app = running_app(Difficulty::Normal);
+        // Long enough for the speed curve to pick up
+        for _ in 0..600 {
+            app.update();
+        }
+        app.world.send_event(GameOverEvent(GameOverReason::Stopped));
+        run_until(&mut app, RunningState::Finished);
+        assert!(app.world.resource::<Time<Virtual>>().is_paused());
+
+        app.world
+            .resource_mut::<NextState<RunningState>>()
+            .set(RunningState::Countdown);
+        run_until(&mut app, RunningState::Running);
+        let time = app.world.resource::<Time<Virtual>>();
+        assert_eq!(time.relative_speed(), 1.0);
+        assert!(!time.is_paused());
+    }
+
+    #[test]
+    fn initial_tiles_per_difficulty() {
+        for &difficulty in Difficulty::ALL {
+            let mut app = headless_app(difficulty);
+            start_session(&mut app, RunningState::Countdown, None);
+            let filled = app.world.resource::<Grid>().filled_tiles();
+            // Tiles placed on an occupied cell would replace the tile there
+            let spawned = app.world.query::<&SpawnedAt>().iter(&app.world).count();
+            assert_eq!(filled, difficulty.initial_tiles(), "{:?}", difficulty);
+            assert_eq!(spawned, difficulty.initial_tiles(), "{:?}", difficulty);
+        }
+    }
+
+    /// Entities of all [`TileExpiredEvent`]s
+    #[derive(Debug, Default, Resource)]
+    struct Expired(Vec<Entity>);
+
+    fn collect_expired(mut events: EventReader<TileExpiredEvent>, mut expired: ResMut<Expired>) {
+        expired.0.extend(events.read().map(|event| event.entity));
+    }
+
+    #[test]
+    fn tiles_expire_once() {
+        let mut app = headless_app(Difficulty::Normal);
+        app.init_resource::<Expired>()
+            .insert_resource(TileExpiry {
+                despawn: false,
+                penalty: 0,
+            })
+            .add_systems(Update, collect_expired);
+        start_session(&mut app, RunningState::Running, None);
+        // Spawn a few tiles, then let all of them lose their points and stay on the field
+        for _ in 0..120 {
+            app.update();
+        }
+        app.world.resource_mut::<SpawnPaused>().0 = true;
+        for _ in 0..600 {
+            app.update();
+        }
+        let mut tiles: Vec<_> = app.world.resource::<Grid>().values().map(|t| t.0).collect();
+        tiles.sort();
+        let mut expired = app.world.resource::<Expired>().0.clone();
+        expired.sort();
+        assert!(!tiles.is_empty());
+        assert_eq!(expired, tiles);
+    }
+
+    /// Number of tiles spawned
+    #[derive(Debug, Default, Resource)]
+    struct Spawns(usize);
+
+    fn count_spawns(spawned: Query<(), Added<SpawnedAt>>, mut spawns: ResMut<Spawns>) {
+        spawns.0 += spawned.iter().count();
+    }
+
+    /// Tiles spawned during the first 10 seconds of a session running at `fps`
+    fn spawns_at(fps: u32) -> usize {
+        let mut app = headless_app(Difficulty::Normal);
+        app.init_resource::<Spawns>()
+            .add_systems(Last, count_spawns);
+        start_session(&mut app, RunningState::Running, Some(fps));
+        for _ in 0..10 * fps {
+            app.update();
+        }
+        app.world.resource::<Spawns>().0
+    }
+
+    #[test]
+    fn spawns_independent_of_frame_rate() {
+        let slow = spawns_at(30);
+        let fast = spawns_at(144);
+        assert!(slow > 0);
+        // The last spawn can fall on either side of the end
+        assert!(
+            slow.abs_diff(fast) <= 1,
+            "{} at 30 fps, {} at 144 fps",
+            slow,
+            fast
+        );
+    }
+
+    fn grid() -> Grid {
+        Grid::new(GridConfig { cols: 4, rows: 3 }, TileTiming::default())
+    }
+
+    #[test]
+    fn new_grid_is_empty() {
+        let grid = grid();
+        assert_eq!(grid.free_tiles(), 12);
+        assert_eq!(grid.filled_tiles(), 0);
+        assert!(!grid.is_full());
+    }
+
+    #[test]
+    fn set_fills_one_tile() {
+        let mut grid = grid();
+        grid.set(1, 2, Entity::from_raw(1), TileKind::Normal);
+        assert!(!grid.is_free(1, 2));
+        assert_eq!(grid.get(1, 2), Some(Entity::from_raw(1)));
+        assert_eq!(grid.free_tiles(), 11);
+        assert_eq!(grid.filled_tiles(), 1);
+    }
+
+    #[test]
+    fn take_empty_tile() {
+        let mut grid = grid();
+        assert_eq!(grid.take(0, 0), None);
+    }
+
+    #[test]
+    fn take_fresh_tile_gives_max_points() {
+        let mut grid = grid();
+        grid.set(3, 0, Entity::from_raw(1), TileKind::Normal);
+        let max = grid.max_points();
+        assert_eq!(max, 10);
+        assert_eq!(
+            grid.take(3, 0),
+            Some((Entity::from_raw(1), max, TileKind::Normal))
+        );
+        assert!(grid.is_free(3, 0));
+    }
+
+    #[test]
+    fn take_decayed_tile_gives_no_points() {
+        let mut grid = grid();
+        grid.set(0, 1, Entity::from_raw(1), TileKind::Normal);
+        let decay = Duration::from_secs_f32(grid.timing.decay_secs);
+        grid.tick(decay + Duration::from_millis(100));
+        assert_eq!(
+            grid.take(0, 1),
+            Some((Entity::from_raw(1), 0, TileKind::Normal))
+        );
+    }
+
+    #[test]
+    fn positions_outside_are_clamped() {
+        let mut grid = grid();
+        assert_eq!(grid.index(10, 10), grid.index(3, 2));
+        assert_eq!(grid.index(10, 0), grid.index(3, 0));
+        grid.set(10, 10, Entity::from_raw(1), TileKind::Normal);
+        assert_eq!(grid.get(3, 2), Some(Entity::from_raw(1)));
+        assert_eq!(grid.filled_tiles(), 1);
+    }
+
+    #[test]
+    fn decay_in_fixed_steps() {
+        let step = Duration::from_secs_f64(1.0 / TICK_RATE);
+        let entity = Entity::from_raw(1);
+        let mut grid = grid();
+        grid.set(0, 0, entity, TileKind::Normal);
+        assert_eq!(
+            grid.values().collect::<Vec<_>>(),
+            [(entity, grid.max_points())]
+        );
+        let steps = (grid.timing.decay_secs as f64 * TICK_RATE).round() as usize;
+        for _ in 1..steps {
+            assert!(grid.tick(step).0.is_empty());
+        }
+        let (expired, _) = grid.tick(step);
+        assert_eq!(expired, [(UVec2::ZERO, entity)]);
+        assert_eq!(grid.take(0, 0), Some((entity, 0, TileKind::Normal)));
+    }
+
+    #[test]
+    fn shuffle_keeps_tiles_and_timers() {
+        let mut grid = grid();
+        for i in 0..3 {
+            grid.set(i, i % 3, Entity::from_raw(i as u32), TileKind::Normal);
+            grid.tick(Duration::from_millis(500));
+        }
+        let remaining = |grid: &Grid| {
+            let mut tiles: Vec<_> = grid
+                .tiles
+                .iter()
+                .flatten()
+                .map(|(entity, timer, _, kind)| (*entity, timer.remaining(), *kind))
+                .collect();
+            tiles.sort_by_key(|(entity, ..)| *entity);
+            tiles
+        };
+        let before = remaining(&grid);
+        let moved = grid.shuffle(&mut StdRng::seed_from_u64(7));
+        assert_eq!(moved.len(), 3);
+        assert_eq!(grid.filled_tiles(), 3);
+        assert_eq!(remaining(&grid), before);
+        for (entity, pos) in moved {
+            assert_eq!(grid.get(pos.x as usize, pos.y as usize), Some(entity));
+        }
+    }
+
+    /// Point timer of a tile with 5 seconds of decay and `left` seconds remaining
+    fn timer_with(left: f32) -> Timer {
+        let mut timer = Timer::from_seconds(5.0, TimerMode::Once);
+        timer.tick(Duration::from_secs_f32(5.0 - left));
+        timer
+    }
+
+    #[test]
+    fn linear_points() {
+        assert_eq!(ScoreFormula::Linear.points(&timer_with(5.0)), 10);
+        assert_eq!(ScoreFormula::Linear.points(&timer_with(2.0)), 4);
+    }
+
+    #[test]
+    fn quadratic_points() {
+        assert_eq!(ScoreFormula::Quadratic.points(&timer_with(5.0)), 10);
+        assert_eq!(ScoreFormula::Quadratic.points(&timer_with(1.6)), 1);
+        assert_eq!(ScoreFormula::Quadratic.points(&timer_with(0.0)), 0);
+    }
+
+    #[test]
+    fn stepped_points() {
+        assert_eq!(ScoreFormula::Stepped.points(&timer_with(5.0)), 10);
+        assert_eq!(ScoreFormula::Stepped.points(&timer_with(4.0)), 10);
+        assert_eq!(ScoreFormula::Stepped.points(&timer_with(3.0)), 6);
+        assert_eq!(ScoreFormula::Stepped.points(&timer_with(0.0)), 0);
+    }
 }
@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use bevy::utils::Duration;
+
+use super::SESSION_STARTS;
+
+/// Plugin flagging sessions with hits in implausibly quick succession, see [`ClickIntervals`]
+pub struct AntiCheatPlugin;
+
+impl Plugin for AntiCheatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ClickIntervals>();
+        for start in SESSION_STARTS {
+            app.add_systems(start, reset_click_intervals);
+        }
+    }
+}
+
+/// Time between the hits of the current session, recorded by [`super::click`].
+/// When [`ClickIntervals::STREAK`] hits in a row each follow the previous one within
+/// [`super::ReactionBounds::min`], faster than anyone can react to a new tile, the session is
+/// marked as suspect and not put on the [`super::Leaderboard`].
+#[derive(Debug, Default, Resource)]
+pub struct ClickIntervals {
+    /// Time between each hit and the one before it
+    pub intervals: Vec<Duration>,
+    /// The session looks assisted
+    pub suspect: bool,
+    /// [`super::GameTime`] of the last hit
+    last_hit: Option<Duration>,
+    /// Number of consecutive intervals below the threshold
+    streak: usize,
+}
+
+impl ClickIntervals {
+    /// Consecutive fast intervals marking the session as suspect
+    pub const STREAK: usize = 5;
+
+    /// Record a hit at `time` since the start of the session, intervals below `min` are too fast
+    /// for a human.
+    /// Hits handled in the same frame, like a batch released by the click delay or a tap with two
+    /// fingers, share their time and don't count as an interval.
+    pub fn record(&mut self, time: Duration, min: Duration) {
+        let Some(last) = self.last_hit.replace(time) else {
+            return;
+        };
+        if time == last {
+            return;
+        }
+        let interval = time.saturating_sub(last);
+        self.intervals.push(interval);
+        if interval < min {
+            self.streak += 1;
+        } else {
+            self.streak = 0;
+        }
+        if self.streak >= Self::STREAK && !self.suspect {
+            warn!(
+                "{} hits in a row within {:?}, the session is marked as assisted",
+                self.streak + 1,
+                min
+            );
+            self.suspect = true;
+        }
+    }
+}
+
+/// Start each session without intervals and unflagged
+fn reset_click_intervals(mut intervals: ResMut<ClickIntervals>) {
+    *intervals = ClickIntervals::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIN: Duration = Duration::from_millis(100);
+
+    /// Intervals after hits every `gap` milliseconds
+    fn hits_every(gap: u64, hits: u64) -> ClickIntervals {
+        let mut intervals = ClickIntervals::default();
+        for i in 0..hits {
+            intervals.record(Duration::from_millis(i * gap), MIN);
+        }
+        intervals
+    }
+
+    #[test]
+    fn fast_streak_is_suspect() {
+        let intervals = hits_every(90, ClickIntervals::STREAK as u64 + 1);
+        assert_eq!(intervals.intervals.len(), ClickIntervals::STREAK);
+        assert!(intervals.suspect);
+    }
+
+    #[test]
+    fn hits_in_the_same_frame_are_no_interval() {
+        let mut intervals = ClickIntervals::default();
+        for _ in 0..10 {
+            intervals.record(Duration::from_secs(1), MIN);
+        }
+        assert!(intervals.intervals.is_empty());
+        assert!(!intervals.suspect);
+    }
+
+    #[test]
+    fn short_streak_or_slow_hits_are_fine() {
+        assert!(!hits_every(90, ClickIntervals::STREAK as u64).suspect);
+        assert!(!hits_every(100, 20).suspect);
+    }
+}
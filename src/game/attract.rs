@@ -0,0 +1,188 @@
+use bevy::ecs::system::SystemParam;
+use bevy::input::mouse::MouseWheel;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::{spawn_position, tile, GameState, Grid, GridConfig, TileKind};
+use crate::despawn_on_screen;
+use crate::settings::{Palette, SpawnPattern};
+
+/// Plugin playing a demo behind the main menu when it is left idle
+pub struct AttractPlugin;
+
+impl Plugin for AttractPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AttractMode>()
+            .add_systems(OnEnter(GameState::Menu), reset_idle)
+            .add_systems(
+                Update,
+                (
+                    track_idle,
+                    start_demo,
+                    run_demo.run_if(resource_exists::<Demo>),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Menu)),
+            )
+            .add_systems(
+                OnExit(GameState::Menu),
+                (despawn_on_screen::<OnDemoScreen>, stop_demo),
+            );
+    }
+}
+
+/// Demo behind the main menu, started after the menu was idle for [`AttractMode::IDLE`] seconds
+/// and stopped by any input
+#[derive(Debug, Resource)]
+pub struct AttractMode {
+    pub enabled: bool,
+    idle: Timer,
+}
+
+impl AttractMode {
+    /// Seconds without input before the demo starts
+    const IDLE: f32 = 15.0;
+    /// Seconds between spawns in the demo
+    const SPAWN_DELAY: f32 = 0.8;
+    /// Seconds between clicks of the bot
+    const CLICK_DELAY: f32 = 0.9;
+}
+
+impl Default for AttractMode {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            idle: Timer::from_seconds(Self::IDLE, TimerMode::Once),
+        }
+    }
+}
+
+/// Tag for the tiles of the demo
+#[derive(Debug, Default, Component)]
+struct OnDemoScreen;
+
+/// State of the running demo, a reduced session without score, sounds or game over
+#[derive(Debug, Resource)]
+struct Demo {
+    grid: Grid,
+    last_spawn: UVec2,
+    spawn: Timer,
+    click: Timer,
+    rng: StdRng,
+}
+
+/// Any input of the player
+#[derive(SystemParam)]
+struct Activity<'w, 's> {
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    mouse: Res<'w, ButtonInput<MouseButton>>,
+    gamepad: Res<'w, ButtonInput<GamepadButton>>,
+    touches: Res<'w, Touches>,
+    cursor: EventReader<'w, 's, CursorMoved>,
+    wheel: EventReader<'w, 's, MouseWheel>,
+}
+
+impl Activity<'_, '_> {
+    /// Check for input since the last frame
+    fn any(&mut self) -> bool {
+        // Read all events, so old ones are not seen again next frame
+        let moved = self.cursor.read().count() > 0;
+        let scrolled = self.wheel.read().count() > 0;
+        moved
+            || scrolled
+            || self.keys.get_pressed().next().is_some()
+            || self.mouse.get_pressed().next().is_some()
+            || self.gamepad.get_pressed().next().is_some()
+            || self.touches.iter().next().is_some()
+    }
+}
+
+fn reset_idle(mut attract: ResMut<AttractMode>) {
+    attract.idle.reset();
+}
+
+/// Count the idle time and stop the demo on input
+fn track_idle(
+    mut commands: Commands,
+    mut attract: ResMut<AttractMode>,
+    mut activity: Activity,
+    time: Res<Time<Real>>,
+    demo: Option<Res<Demo>>,
+    tiles: Query<Entity, With<OnDemoScreen>>,
+) {
+    if !activity.any() {
+        attract.idle.tick(time.delta());
+        return;
+    }
+    attract.idle.reset();
+    if demo.is_some() {
+        commands.remove_resource::<Demo>();
+        for entity in &tiles {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Start the demo once the menu was idle long enough
+fn start_demo(
+    mut commands: Commands,
+    attract: Res<AttractMode>,
+    demo: Option<Res<Demo>>,
+    config: Res<GridConfig>,
+) {
+    if !attract.enabled || !attract.idle.finished() || demo.is_some() {
+        return;
+    }
+    info!("Starting the main menu demo");
+    commands.insert_resource(Demo {
+        grid: Grid::new(*config, default()),
+        last_spawn: UVec2::default(),
+        spawn: Timer::from_seconds(AttractMode::SPAWN_DELAY, TimerMode::Repeating),
+        click: Timer::from_seconds(AttractMode::CLICK_DELAY, TimerMode::Repeating),
+        rng: StdRng::seed_from_u64(rand::random()),
+    });
+}
+
+/// Spawn tiles and let the bot click the tile closest to losing all of its points
+fn run_demo(
+    mut commands: Commands,
+    mut demo: ResMut<Demo>,
+    time: Res<Time<Real>>,
+    palette: Res<Palette>,
+) {
+    let demo = &mut *demo;
+    let (expired, _) = demo.grid.tick(time.delta());
+    for (pos, entity) in expired {
+        demo.grid.take(pos.x as usize, pos.y as usize);
+        commands.entity(entity).despawn_recursive();
+    }
+    if demo.spawn.tick(time.delta()).just_finished() {
+        if let Some(pos) = spawn_position(
+            &demo.grid,
+            demo.last_spawn,
+            SpawnPattern::Clustered,
+            &mut demo.rng,
+        ) {
+            // Faded, so the demo stays in the background of the menu
+            let color = palette.tile.with_a(0.4);
+            let entity =
+                tile::<OnDemoScreen>(&mut commands, &demo.grid.config, pos.extend(1), color);
+            demo.grid
+                .set(pos.x as usize, pos.y as usize, entity, TileKind::Normal);
+            demo.last_spawn = pos;
+        }
+    }
+    if demo.click.tick(time.delta()).just_finished() {
+        let Some((pos, _)) = demo.grid.most_urgent() else {
+            return;
+        };
+        if let Some((entity, _, _)) = demo.grid.take(pos.x as usize, pos.y as usize) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn stop_demo(mut commands: Commands) {
+    commands.remove_resource::<Demo>();
+}
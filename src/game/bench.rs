@@ -0,0 +1,223 @@
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use bevy::utils::Duration;
+use bevy::window::ExitCondition;
+
+use super::input::{ClickEvent, LiveInput};
+use super::{
+    Assets, Difficulty, GamePlugin, GameState, GameTime, Grid, RunningState, Score, SpawnedAt,
+};
+use crate::settings::SettingsPlugin;
+use crate::storage::Storage;
+
+/// Length of a simulated frame
+const FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+/// Age of a tile before the bot clicks it.
+/// Without it the bot clears every tile in the step it spawns, which spawns the next one at once.
+const REACTION: Duration = Duration::from_millis(300);
+
+/// Play `sessions` timed sessions of `difficulty` without a window, with a bot clicking the
+/// oldest tile once it is [`REACTION`] old, at 60 frames per second of simulated time.
+/// Nothing is stored, see [`headless_app`].
+pub fn bench_ai(difficulty: Difficulty, sessions: usize) -> BenchSummary {
+    let mut app = headless_app(difficulty);
+    app.add_systems(
+        FixedUpdate,
+        bot_click
+            .before(super::click)
+            .run_if(in_state(RunningState::Running)),
+    );
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Game);
+    let mut scores = Vec::with_capacity(sessions);
+    while scores.len() < sessions {
+        app.update();
+        if *app.world.resource::<State<RunningState>>().get() == RunningState::Finished {
+            scores.push(app.world.resource::<Score>().0);
+            app.world
+                .resource_mut::<NextState<RunningState>>()
+                .set(RunningState::Countdown);
+        }
+    }
+    BenchSummary::new(difficulty, scores)
+}
+
+/// App running the game of `difficulty` without a window or assets, advancing 1/60s of time
+/// with every `app.update()`.
+/// The live input is disabled during sessions, clicks have to be sent as [`ClickEvent`]s.
+/// Setting [`GameState::Game`] starts the first session, systems added to the returned app
+/// still run.
+/// Nothing is loaded or stored, every app starts without lifetime statistics, leaderboard and
+/// high score and leaves the stored ones untouched.
+pub fn headless_app(difficulty: Difficulty) -> App {
+    let mut app = App::new();
+    app.insert_resource(Storage::Disabled)
+        .add_plugins((
+            MinimalPlugins,
+            bevy::input::InputPlugin,
+            WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                close_when_requested: false,
+            },
+        ))
+        .add_plugins((SettingsPlugin, GamePlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME))
+        .insert_resource(difficulty)
+        .insert_resource(Assets {
+            font: default(),
+            hit: default(),
+            error: default(),
+            spawn: default(),
+            tick: default(),
+            bonus: default(),
+            time_bonus: default(),
+            warning: default(),
+        })
+        .add_systems(OnEnter(RunningState::Running), disable_live_input);
+    app.finish();
+    app.cleanup();
+    app
+}
+
+/// Start the first session of a [`headless_app`] at `fps` frames per second, 60 without, and run
+/// frames until it reaches `state`
+#[cfg(test)]
+pub(crate) fn start_session(app: &mut App, state: RunningState, fps: Option<u32>) {
+    if let Some(fps) = fps {
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(
+            Duration::from_secs(1) / fps,
+        ));
+    }
+    app.world
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Game);
+    run_until(app, state);
+}
+
+/// Run frames until the session reaches `state`, panics when it takes more than 1000 frames
+#[cfg(test)]
+pub(crate) fn run_until(app: &mut App, state: RunningState) {
+    for _ in 0..1000 {
+        app.update();
+        if *app.world.resource::<State<RunningState>>().get() == state {
+            return;
+        }
+    }
+    panic!("{:?} was not reached", state);
+}
+
+/// Scores of the sessions of a [`bench_ai`] run
+#[derive(Debug, Clone)]
+pub struct BenchSummary {
+    pub difficulty: Difficulty,
+    /// Scores sorted in increasing order
+    pub scores: Vec<usize>,
+}
+
+impl BenchSummary {
+    fn new(difficulty: Difficulty, mut scores: Vec<usize>) -> Self {
+        scores.sort_unstable();
+        Self { difficulty, scores }
+    }
+
+    pub fn min(&self) -> usize {
+        self.scores.first().copied().unwrap_or(0)
+    }
+
+    pub fn max(&self) -> usize {
+        self.scores.last().copied().unwrap_or(0)
+    }
+
+    pub fn median(&self) -> usize {
+        self.scores.get(self.scores.len() / 2).copied().unwrap_or(0)
+    }
+
+    pub fn mean(&self) -> f32 {
+        if self.scores.is_empty() {
+            return 0.0;
+        }
+        self.scores.iter().sum::<usize>() as f32 / self.scores.len() as f32
+    }
+}
+
+impl std::fmt::Display for BenchSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?}: {} sessions, min {}, median {}, mean {:.1}, max {}",
+            self.difficulty,
+            self.scores.len(),
+            self.min(),
+            self.median(),
+            self.mean(),
+            self.max()
+        )
+    }
+}
+
+/// There is no window to take input from, the clicks come from the bot
+fn disable_live_input(mut live: ResMut<LiveInput>) {
+    live.0 = false;
+}
+
+/// Click the tile closest to losing all of its points, which is the oldest one
+fn bot_click(
+    tiles: Res<Grid>,
+    time: Res<GameTime>,
+    spawned_at: Query<&SpawnedAt>,
+    mut clicks: EventWriter<ClickEvent>,
+) {
+    let Some((pos, (entity, ..))) = tiles.most_urgent() else {
+        return;
+    };
+    let seen = spawned_at
+        .get(*entity)
+        .map_or(true, |spawned| time.0.elapsed() >= spawned.0 + REACTION);
+    if seen {
+        clicks.send(ClickEvent {
+            tile_x: pos.x as usize,
+            tile_y: pos.y as usize,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hit_scores_and_misclick_ends_session() {
+        let mut app = headless_app(Difficulty::Normal);
+        start_session(&mut app, RunningState::Running, None);
+        // Wait for the spawn timer
+        while app.world.resource::<Grid>().filled_tiles() == 0 {
+            app.update();
+        }
+        let (pos, _) = app.world.resource::<Grid>().most_urgent().unwrap();
+        app.world.send_event(ClickEvent {
+            tile_x: pos.x as usize,
+            tile_y: pos.y as usize,
+        });
+        app.update();
+        assert!(app.world.resource::<Score>().0 > 0);
+
+        let grid = app.world.resource::<Grid>();
+        let (cols, rows) = (grid.config.cols, grid.config.rows);
+        let empty = (0..rows)
+            .flat_map(|y| (0..cols).map(move |x| (x, y)))
+            .find(|&(x, y)| grid.is_free(x, y))
+            .unwrap();
+        app.world.send_event(ClickEvent {
+            tile_x: empty.0,
+            tile_y: empty.1,
+        });
+        app.update();
+        app.update();
+        assert_eq!(
+            *app.world.resource::<State<RunningState>>().get(),
+            RunningState::Finished
+        );
+    }
+}
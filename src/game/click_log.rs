@@ -0,0 +1,132 @@
+use bevy::prelude::*;
+use bevy::utils::Duration;
+
+use super::{GameOverEvent, RunningState, SESSION_STARTS};
+use crate::storage;
+
+/// Plugin recording every click of a session and exporting them as CSV at game over
+pub struct ClickLogPlugin;
+
+impl Plugin for ClickLogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ClickLogging>()
+            .init_resource::<ClickLog>()
+            .add_systems(
+                PostUpdate,
+                export_click_log.run_if(in_state(RunningState::Running)),
+            );
+        for start in SESSION_STARTS {
+            app.add_systems(start, reset_click_log);
+        }
+    }
+}
+
+/// Configuration of the click log, disabled by default
+#[derive(Debug, Resource)]
+pub struct ClickLogging {
+    pub enabled: bool,
+    /// File the log is written to, or the name of the download in the browser
+    pub path: String,
+}
+
+impl Default for ClickLogging {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "clicks.csv".into(),
+        }
+    }
+}
+
+/// Result of a single click
+#[derive(Debug, Clone, Copy)]
+pub enum ClickOutcome {
+    /// An occupied tile was clicked
+    Hit,
+    /// An empty tile was clicked during the warm-up or in practice
+    Forgiven,
+    /// An empty tile was clicked, costing one of the [`super::Lives`]
+    Miss,
+    /// The tile was too small to be clicked
+    Ignored,
+}
+
+impl ClickOutcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Hit => "hit",
+            Self::Forgiven => "forgiven",
+            Self::Miss => "miss",
+            Self::Ignored => "ignored",
+        }
+    }
+}
+
+/// One handled click
+#[derive(Debug, Clone)]
+pub struct ClickRecord {
+    /// Real time since the start of the session
+    pub time: Duration,
+    pub x: usize,
+    pub y: usize,
+    pub outcome: ClickOutcome,
+    /// Time since the clicked tile spawned, if there was one
+    pub tile_age: Option<Duration>,
+}
+
+/// Clicks of the current session
+#[derive(Debug, Default, Resource)]
+pub struct ClickLog(Vec<ClickRecord>);
+
+impl ClickLog {
+    /// Record a click, does nothing when logging is disabled
+    pub fn record(&mut self, config: &ClickLogging, record: ClickRecord) {
+        if config.enabled {
+            self.0.push(record);
+        }
+    }
+
+    /// Render the log as CSV including a header row
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("time_ms,x,y,result,tile_age_ms\n");
+        for record in &self.0 {
+            let age = record
+                .tile_age
+                .map_or(String::new(), |age| age.as_millis().to_string());
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                record.time.as_millis(),
+                record.x,
+                record.y,
+                record.outcome.as_str(),
+                age
+            ));
+        }
+        csv
+    }
+}
+
+/// Start each session with an empty log
+fn reset_click_log(mut log: ResMut<ClickLog>) {
+    log.0.clear();
+}
+
+/// Export the log of the finished session
+fn export_click_log(
+    mut events: EventReader<GameOverEvent>,
+    log: Res<ClickLog>,
+    config: Res<ClickLogging>,
+) {
+    if events.is_empty() {
+        return;
+    }
+    events.clear();
+    if !config.enabled {
+        return;
+    }
+    if log.0.is_empty() {
+        info!("No clicks recorded, skipping export of {}", config.path);
+        return;
+    }
+    storage::export(&config.path, &log.to_csv());
+}
@@ -0,0 +1,187 @@
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+
+use super::{GameState, GameTime, Grid, GridConfig, OnGameScreen};
+
+/// Plugin showing frame rate and session internals in a corner, toggled with [`DebugOverlay::KEY`],
+/// and the index of every tile of the field, toggled with [`TileCoordinates::KEY`]
+pub struct DebugOverlayPlugin;
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin);
+        }
+        app.init_resource::<DebugOverlay>()
+            .init_resource::<TileCoordinates>()
+            .add_systems(
+                Update,
+                (
+                    toggle_debug_overlay,
+                    show_debug_overlay.run_if(resource_changed::<DebugOverlay>),
+                    update_debug_overlay.run_if(|overlay: Res<DebugOverlay>| overlay.0),
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    toggle_tile_coordinates,
+                    show_tile_coordinates.run_if(
+                        in_state(GameState::Game).and_then(resource_changed::<TileCoordinates>),
+                    ),
+                )
+                    .chain(),
+            )
+            .add_systems(OnEnter(GameState::Game), show_tile_coordinates);
+    }
+}
+
+/// Whether the diagnostics overlay is shown, hidden by default
+#[derive(Debug, Default, Resource)]
+pub struct DebugOverlay(pub bool);
+
+impl DebugOverlay {
+    /// Key toggling the overlay
+    pub const KEY: KeyCode = KeyCode::F3;
+}
+
+/// Marker of the overlay text
+#[derive(Component)]
+struct DebugOverlayText;
+
+/// Whether the `(x, y)` index of each tile is drawn on the field, hidden by default.
+/// Shows which tile a click on the screen is expected to map to.
+#[derive(Debug, Default, Resource)]
+pub struct TileCoordinates(pub bool);
+
+impl TileCoordinates {
+    /// Key toggling the tile indices
+    pub const KEY: KeyCode = KeyCode::F4;
+}
+
+/// Marker of the index text on a tile
+#[derive(Component)]
+struct TileCoordinateLabel;
+
+fn toggle_debug_overlay(keys: Res<ButtonInput<KeyCode>>, mut overlay: ResMut<DebugOverlay>) {
+    if keys.just_pressed(DebugOverlay::KEY) {
+        overlay.0 = !overlay.0;
+    }
+}
+
+/// Spawn or despawn the overlay text to match [`DebugOverlay`]
+fn show_debug_overlay(
+    mut commands: Commands,
+    overlay: Res<DebugOverlay>,
+    texts: Query<Entity, With<DebugOverlayText>>,
+) {
+    for entity in &texts {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !overlay.0 {
+        return;
+    }
+    // The default font does not depend on loaded assets, so the overlay works in every state
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 14.0,
+                color: Color::YELLOW,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(4.0),
+            bottom: Val::Px(4.0),
+            ..default()
+        })
+        .with_background_color(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+        ZIndex::Global(i32::MAX),
+        DebugOverlayText,
+    ));
+}
+
+/// Refresh the overlay, session values are only shown while a session exists
+fn update_debug_overlay(
+    mut texts: Query<&mut Text, With<DebugOverlayText>>,
+    diagnostics: Res<DiagnosticsStore>,
+    time: Res<Time<Virtual>>,
+    grid: Option<Res<Grid>>,
+    game_time: Option<Res<GameTime>>,
+) {
+    let fps = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .map_or("-".into(), |fps| format!("{:.0}", fps));
+    let mut lines = vec![
+        format!("FPS: {}", fps),
+        format!(
+            "Speed: {:.2}{}",
+            time.relative_speed(),
+            if time.is_paused() { " (paused)" } else { "" }
+        ),
+    ];
+    if let Some(grid) = grid {
+        lines.push(format!(
+            "Tiles: {} free, {} filled",
+            grid.free_tiles(),
+            grid.filled_tiles()
+        ));
+    }
+    if let Some(game_time) = game_time {
+        lines.push(format!("Game time: {:.2}s", game_time.0.elapsed_secs()));
+    }
+    for mut text in &mut texts {
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+fn toggle_tile_coordinates(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut coordinates: ResMut<TileCoordinates>,
+) {
+    if keys.just_pressed(TileCoordinates::KEY) {
+        coordinates.0 = !coordinates.0;
+    }
+}
+
+/// Spawn or despawn the index labels of the field to match [`TileCoordinates`]
+fn show_tile_coordinates(
+    mut commands: Commands,
+    coordinates: Res<TileCoordinates>,
+    config: Res<GridConfig>,
+    labels: Query<Entity, With<TileCoordinateLabel>>,
+) {
+    for entity in &labels {
+        commands.entity(entity).despawn_recursive();
+    }
+    if !coordinates.0 {
+        return;
+    }
+    let size = config.size();
+    for y in 0..size.y {
+        for x in 0..size.x {
+            // Above the tiles and the keyboard highlight
+            let center = config.cell_to_world(UVec2::new(x, y).as_vec2() + 0.5);
+            commands.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        format!("({}, {})", x, y),
+                        TextStyle {
+                            font_size: 12.0,
+                            color: Color::FUCHSIA,
+                            ..default()
+                        },
+                    ),
+                    transform: Transform::from_translation(center.extend(4.0)),
+                    ..default()
+                },
+                TileCoordinateLabel,
+                OnGameScreen,
+            ));
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::GAME_DURATION;
+
+/// Difficulty of a session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Resource)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    /// All difficulties in increasing order
+    pub const ALL: &'static [Self] = &[Self::Easy, Self::Normal, Self::Hard];
+
+    /// Number of tiles on the field when a session starts
+    pub fn initial_tiles(self) -> usize {
+        match self {
+            Self::Easy | Self::Normal => 0,
+            Self::Hard => 3,
+        }
+    }
+
+    /// Increase of the relative speed during the session
+    pub fn speed_curve(self) -> SpeedCurve {
+        // Normal reaches its maximum at the end of a timed session
+        let acceleration = 2.0 / GAME_DURATION.powi(2);
+        match self {
+            Self::Easy => SpeedCurve {
+                acceleration: acceleration * 0.75,
+                max_speed: 2.0,
+            },
+            Self::Normal => SpeedCurve {
+                acceleration,
+                max_speed: 3.0,
+            },
+            Self::Hard => SpeedCurve {
+                acceleration: acceleration * 1.5,
+                max_speed: 4.0,
+            },
+        }
+    }
+
+    /// Seconds between timed spawns
+    pub fn spawn_delay(self) -> f32 {
+        match self {
+            Self::Easy => 1.0,
+            Self::Normal => 0.8,
+            Self::Hard => 0.65,
+        }
+    }
+
+    /// Number of tiles per timed spawn late in the session
+    pub fn multi_spawn(self) -> MultiSpawn {
+        match self {
+            Self::Easy => MultiSpawn {
+                start: 25.0,
+                interval: 15.0,
+                max: 2,
+            },
+            Self::Normal => MultiSpawn {
+                start: 20.0,
+                interval: 15.0,
+                max: 2,
+            },
+            Self::Hard => MultiSpawn {
+                start: 15.0,
+                interval: 10.0,
+                max: 3,
+            },
+        }
+    }
+
+    /// Number of misclicks which end the session
+    pub fn lives(self) -> usize {
+        match self {
+            Self::Easy => 3,
+            Self::Normal | Self::Hard => 1,
+        }
+    }
+
+    /// How long tiles are worth points and stay on the field
+    pub fn tile_timing(self) -> TileTiming {
+        TileTiming::default()
+    }
+
+    /// Whether [`super::TileKind::Moving`] tiles can spawn
+    pub fn moving_tiles(self) -> bool {
+        self == Self::Hard
+    }
+
+    /// Whether a tile losing all of its points ends the session
+    pub fn fatal_expiry(self) -> bool {
+        self == Self::Hard
+    }
+
+    /// The next harder difficulty, wrapping around to the easiest
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|d| *d == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+/// Timing of a single tile in seconds of [`Time<Virtual>`], see [`Difficulty::tile_timing`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileTiming {
+    /// Seconds until a tile is worth zero points, see [`super::ScoreFormula`] for the points
+    /// before that
+    pub decay_secs: f32,
+    /// Seconds until a tile disappears, `None` leaves it to [`super::TileExpiry::despawn`]
+    pub lifetime_secs: Option<f32>,
+}
+
+impl Default for TileTiming {
+    fn default() -> Self {
+        Self {
+            decay_secs: 5.0,
+            lifetime_secs: None,
+        }
+    }
+}
+
+/// What the relative speed of [`Time<Virtual>`] ramps up with during a session.
+/// Both factors are multiplied and capped at [`SpeedCurve::max_speed`].
+/// Spawns and tile decay run on virtual time, so they speed up with it.
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct Ramp {
+    /// Follow the [`SpeedCurve`] over the elapsed session time
+    pub by_time: bool,
+    /// Speed up by 1 for every [`Ramp::POINTS_PER_SPEED`] points of the score
+    pub by_score: bool,
+}
+
+impl Ramp {
+    /// Points which add 1 to the relative speed with [`Ramp::by_score`]
+    pub const POINTS_PER_SPEED: f32 = 200.0;
+
+    /// Target relative speed after `elapsed` seconds with `score` points
+    pub fn relative_speed(&self, curve: &SpeedCurve, elapsed: f32, score: usize) -> f32 {
+        let time = if self.by_time {
+            curve.relative_speed(elapsed)
+        } else {
+            1.0
+        };
+        let score = if self.by_score {
+            1.0 + score as f32 / Self::POINTS_PER_SPEED
+        } else {
+            1.0
+        };
+        (time * score).min(curve.max_speed)
+    }
+}
+
+impl Default for Ramp {
+    fn default() -> Self {
+        Self {
+            by_time: true,
+            by_score: false,
+        }
+    }
+}
+
+/// Relative speed of [`Time<Virtual>`] during a session, see [`Difficulty::speed_curve`].
+// t_r(t) = a t² + 1, held at the maximum once reached
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct SpeedCurve {
+    /// Quadratic coefficient `a`
+    pub acceleration: f32,
+    /// Relative speed at which the curve levels off
+    pub max_speed: f32,
+}
+
+impl SpeedCurve {
+    /// Relative speed after `elapsed` seconds of real time
+    pub fn relative_speed(&self, elapsed: f32) -> f32 {
+        (self.acceleration * elapsed.powi(2) + 1.0).min(self.max_speed)
+    }
+
+    /// Seconds after the start of the session at which the maximum speed is reached
+    pub fn plateau_start(&self) -> f32 {
+        ((self.max_speed - 1.0).max(0.0) / self.acceleration).sqrt()
+    }
+}
+
+impl Default for SpeedCurve {
+    fn default() -> Self {
+        Difficulty::default().speed_curve()
+    }
+}
+
+/// Tiles spawned at once by the spawn timer, see [`Difficulty::multi_spawn`]
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct MultiSpawn {
+    /// Seconds of [`super::GameTime`] after which a second tile is added
+    pub start: f32,
+    /// Seconds after which each further tile is added
+    pub interval: f32,
+    /// Maximum number of tiles per spawn
+    pub max: usize,
+}
+
+impl MultiSpawn {
+    /// Tiles per spawn after `elapsed` seconds of the session
+    pub fn count(&self, elapsed: f32) -> usize {
+        if elapsed < self.start {
+            return 1;
+        }
+        let extra = ((elapsed - self.start) / self.interval) as usize;
+        (2 + extra).min(self.max)
+    }
+}
+
+/// Rate at which the applied relative speed follows its target, `None` applies it instantly.
+/// With a rate `r` the remaining difference shrinks by the factor `e^(-r)` every second,
+/// so jumps of the target (e.g. an expiring power-up) become gradual.
+#[derive(Debug, Default, Resource)]
+pub struct SpeedSmoothing(pub Option<f32>);
+
+impl SpeedSmoothing {
+    /// Relative speed to apply after `delta` seconds when moving from `current` to `target`
+    pub fn apply(&self, current: f32, target: f32, delta: f32) -> f32 {
+        match self.0 {
+            Some(rate) => current + (target - current) * (1.0 - (-rate * delta).exp()),
+            None => target,
+        }
+    }
+}
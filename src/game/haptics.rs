@@ -0,0 +1,47 @@
+use bevy::prelude::*;
+
+use super::SoundEvent;
+use crate::settings::Settings;
+
+/// Plugin vibrating the device on hits and misclicks with [`Settings::haptics`].
+/// Only the browser can vibrate, on other platforms this does nothing.
+pub struct HapticsPlugin;
+
+impl Plugin for HapticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, haptic_feedback);
+    }
+}
+
+/// Milliseconds to vibrate when a tile is hit
+const HIT_VIBRATION: u32 = 20;
+/// Milliseconds to vibrate on a misclick, long enough to feel different from a hit
+const ERROR_VIBRATION: u32 = 150;
+
+/// Vibrate for the hit and error sounds, also when the sounds are muted
+fn haptic_feedback(mut events: EventReader<SoundEvent>, settings: Res<Settings>) {
+    if !settings.haptics {
+        events.clear();
+        return;
+    }
+    for sound in events.read() {
+        match sound {
+            SoundEvent::Normal | SoundEvent::Bonus | SoundEvent::TimeBonus => {
+                vibrate(HIT_VIBRATION)
+            }
+            SoundEvent::Error => vibrate(ERROR_VIBRATION),
+            SoundEvent::Spawn | SoundEvent::Tick | SoundEvent::Warning => {}
+        }
+    }
+}
+
+/// Vibrate with `navigator.vibrate`, which is ignored by devices that cannot vibrate
+#[cfg(target_arch = "wasm32")]
+fn vibrate(millis: u32) {
+    if let Some(window) = web_sys::window() {
+        window.navigator().vibrate_with_duration(millis);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn vibrate(_millis: u32) {}
@@ -1,11 +1,22 @@
+use bevy::ecs::system::SystemParam;
+use bevy::input::gamepad::{GamepadConnection, GamepadConnectionEvent};
 use bevy::input::touch::TouchPhase;
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use bevy::window::{PrimaryWindow, WindowResized};
+use serde::{Deserialize, Serialize};
+
+use crate::despawn_on_screen;
+use crate::settings::{Action, KeyBindings};
 
 /// Plugin which convert input clicks to a tile clicked event
 pub struct InputPlugin<S> {
     state: S,
     size: UVec2,
     field: (Vec2, Vec2),
+    inset: f32,
+    pause: Option<S>,
+    countdown: Option<S>,
 }
 
 impl<S> InputPlugin<S> {
@@ -15,8 +26,34 @@ impl<S> InputPlugin<S> {
             state,
             size,
             field: (top_left, bottom_right),
+            inset: 0.0,
+            pause: None,
+            countdown: None,
         }
     }
+
+    /// State in which the game is paused.
+    /// Entering the input state from it does not start a new [`ClickDelay`], so the first
+    /// click after resuming is not swallowed.
+    pub fn with_pause(mut self, pause: S) -> Self {
+        self.pause = Some(pause);
+        self
+    }
+
+    /// State counting down before the input state.
+    /// The countdown gives the player time to get ready, so entering the input state from it
+    /// does not start a [`ClickDelay`].
+    pub fn with_countdown(mut self, countdown: S) -> Self {
+        self.countdown = Some(countdown);
+        self
+    }
+
+    /// Shrink the clickable field by `inset` on every side of the region.
+    /// Clicks in the margin are ignored and the tiles are divided over the remaining area.
+    pub fn with_inset(mut self, inset: f32) -> Self {
+        self.inset = inset.max(0.0);
+        self
+    }
 }
 
 impl<S> Plugin for InputPlugin<S>
@@ -25,45 +62,310 @@ where
 {
     fn build(&self, app: &mut App) {
         app.add_event::<ClickEvent>()
+            .add_event::<RawClickEvent>()
+            .insert_resource(DebugInput::from_env())
             .insert_resource(Grid {
                 size: self.size,
                 field: self.field,
+                inset: self.inset,
             })
+            .init_resource::<InputDedup>()
+            .init_resource::<LastTouch>()
+            .init_resource::<KeyboardCursor>()
+            .init_resource::<LiveInput>()
+            .init_resource::<UiScale>()
+            .add_systems(Update, log_gamepad_connections)
+            .add_systems(Update, fit_to_window.before(handle_touch_input))
+            .add_systems(
+                Update,
+                (
+                    handle_touch_input,
+                    handle_click_input,
+                    handle_keyboard_input,
+                )
+                    .chain()
+                    .run_if(in_state(self.state).and_then(|live: Res<LiveInput>| live.0)),
+            )
             .add_systems(
                 Update,
-                (handle_click_input, handle_touch_input).run_if(in_state(self.state)),
+                show_click_marker
+                    .after(handle_keyboard_input)
+                    .run_if(in_state(self.state).and_then(|debug: Res<DebugInput>| debug.0)),
             )
             .add_systems(PreUpdate, update_time.run_if(in_state(self.state)))
-            .add_systems(OnEnter(self.state), setup);
+            .add_systems(OnEnter(self.state), (setup, spawn_keyboard_highlight))
+            .add_systems(
+                OnExit(self.state),
+                (
+                    despawn_on_screen::<KeyboardHighlight>,
+                    despawn_on_screen::<ClickMarker>,
+                ),
+            );
+        if let Some(pause) = self.pause {
+            app.add_systems(OnExit(pause), mark_resumed);
+        }
+        if let Some(countdown) = self.countdown {
+            app.add_systems(OnExit(countdown), mark_counted_down);
+        }
     }
 }
 
 /// The grid settings
 #[derive(Debug, Resource)]
-struct Grid {
-    size: UVec2,
+pub struct Grid {
+    /// Number of tiles in each direction, can be changed while the plugin is running
+    pub size: UVec2,
+    /// Region of the tiles in the window the game is laid out for, which starts at the origin.
+    /// [`fit_to_window`] scales the view so the region keeps covering the same tiles in any
+    /// window.
     field: (Vec2, Vec2),
+    /// Margin inside `field` which is not clickable
+    inset: f32,
+}
+
+/// Whether mouse, touch, keyboard and gamepad input sends [`ClickEvent`]s.
+/// Disabled while the clicks come from somewhere else, like a replay.
+#[derive(Debug, Resource)]
+pub struct LiveInput(pub bool);
+
+impl Default for LiveInput {
+    fn default() -> Self {
+        Self(true)
+    }
 }
 
 /// Event with clicked tile positions
-#[derive(Debug, Event)]
+#[derive(Debug, Clone, Event, Serialize, Deserialize)]
 pub struct ClickEvent {
     pub tile_x: usize,
     pub tile_y: usize,
 }
 
-/// Delay to prevent click events being send for 0.4s after the game stars
+/// Mouse click or touch before it is mapped to a tile, only sent with [`DebugInput`]
+#[derive(Debug, Event)]
+pub struct RawClickEvent {
+    /// Position on the screen in logical pixels
+    pub position: Vec2,
+    /// Position in world coordinates, also when outside of the field
+    pub world_pos: Vec2,
+}
+
+/// Send a [`RawClickEvent`] for every click and mark the last one on the screen,
+/// to verify the mapping of screen coordinates.
+/// Enabled by setting the environment variable [`DebugInput::ENV`].
+#[derive(Debug, Default, Resource)]
+pub struct DebugInput(pub bool);
+
+impl DebugInput {
+    /// Environment variable enabling the debug output
+    const ENV: &'static str = "TILE_CLICKER_DEBUG_INPUT";
+
+    fn from_env() -> Self {
+        Self(std::env::var_os(Self::ENV).is_some())
+    }
+}
+
+/// Cross at the world position of the last [`RawClickEvent`]
+#[derive(Debug, Component)]
+struct ClickMarker;
+
+/// Deduplication of mouse and touch input.
+/// Devices with a touchscreen can report a single tap as both a touch and a mouse click.
+#[derive(Debug, Resource)]
+pub struct InputDedup {
+    /// Mouse clicks within this time after a touch are ignored
+    pub window: Duration,
+}
+
+impl Default for InputDedup {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Real time of the last handled touch
+#[derive(Debug, Default, Resource)]
+struct LastTouch(Option<Duration>);
+
+/// Tile focused by the keyboard, moved and clicked with the keys of the [`KeyBindings`]
+#[derive(Debug, Default, Resource)]
+struct KeyboardCursor(UVec2);
+
+/// Sprite over the tile of the [`KeyboardCursor`], hidden until the keyboard is used
+#[derive(Debug, Component)]
+struct KeyboardHighlight;
+
+/// Tilt of the left stick counting as a direction
+const STICK_THRESHOLD: f32 = 0.5;
+
+/// Navigation input of all connected gamepads.
+/// Without the `gamepad` feature no gamepads are ever connected.
+#[derive(SystemParam)]
+pub struct GamepadInput<'w, 's> {
+    gamepads: Res<'w, Gamepads>,
+    buttons: Res<'w, ButtonInput<GamepadButton>>,
+    axes: Res<'w, Axis<GamepadAxis>>,
+    /// Direction of the left stick in the previous frame, to move once per tilt
+    stick: Local<'s, IVec2>,
+}
+
+impl GamepadInput<'_, '_> {
+    /// Direction pressed this frame on the D-pad or by tilting the left stick, y pointing down
+    pub fn direction(&mut self) -> IVec2 {
+        let mut direction = IVec2::ZERO;
+        let mut stick = IVec2::ZERO;
+        for gamepad in self.gamepads.iter() {
+            let pressed = |button| {
+                self.buttons
+                    .just_pressed(GamepadButton::new(gamepad, button))
+            };
+            direction.x += pressed(GamepadButtonType::DPadRight) as i32;
+            direction.x -= pressed(GamepadButtonType::DPadLeft) as i32;
+            direction.y += pressed(GamepadButtonType::DPadDown) as i32;
+            direction.y -= pressed(GamepadButtonType::DPadUp) as i32;
+            let axis = |axis| {
+                let value = self
+                    .axes
+                    .get(GamepadAxis::new(gamepad, axis))
+                    .unwrap_or(0.0);
+                if value.abs() < STICK_THRESHOLD {
+                    0
+                } else {
+                    value.signum() as i32
+                }
+            };
+            stick.x += axis(GamepadAxisType::LeftStickX);
+            stick.y -= axis(GamepadAxisType::LeftStickY);
+        }
+        let stick = stick.clamp(IVec2::NEG_ONE, IVec2::ONE);
+        // Only the moment the stick is tilted counts, not holding it
+        if stick != *self.stick {
+            if stick.x != self.stick.x {
+                direction.x += stick.x;
+            }
+            if stick.y != self.stick.y {
+                direction.y += stick.y;
+            }
+            *self.stick = stick;
+        }
+        direction.clamp(IVec2::NEG_ONE, IVec2::ONE)
+    }
+
+    /// Whether the south button (A on most controllers) was pressed this frame
+    pub fn confirm(&self) -> bool {
+        self.gamepads.iter().any(|gamepad| {
+            self.buttons
+                .just_pressed(GamepadButton::new(gamepad, GamepadButtonType::South))
+        })
+    }
+}
+
+/// Delay to prevent click events being send for 0.4s after the game stars.
+/// The first click during the delay is kept and sent once it finished, so it is not lost.
 #[derive(Debug, Resource)]
-struct ClickDelay(Timer);
+struct ClickDelay {
+    timer: Timer,
+    pending: Option<ClickEvent>,
+}
+
+impl ClickDelay {
+    fn new(secs: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(secs, TimerMode::Once),
+            pending: None,
+        }
+    }
+
+    /// Send the click, or keep it until the delay finished if no other click is waiting
+    fn submit(&mut self, click: ClickEvent, events: &mut EventWriter<ClickEvent>) {
+        if self.timer.finished() {
+            events.send(click);
+        } else if self.pending.is_none() {
+            self.pending = Some(click);
+        }
+    }
+}
+
+/// Marker for entering the input state by resuming from the pause state
+#[derive(Debug, Resource)]
+struct Resumed;
+
+/// Marker for entering the input state after the countdown state
+#[derive(Debug, Resource)]
+struct CountedDown;
+
+/// Setup plugin resources, unless resuming with the [`ClickDelay`] already finished
+fn setup(
+    mut commands: Commands,
+    resumed: Option<Res<Resumed>>,
+    counted_down: Option<Res<CountedDown>>,
+) {
+    if resumed.is_some() {
+        commands.remove_resource::<Resumed>();
+        return;
+    }
+    let delay = if counted_down.is_some() {
+        commands.remove_resource::<CountedDown>();
+        0.0
+    } else {
+        0.4
+    };
+    commands.insert_resource(ClickDelay::new(delay));
+    commands.insert_resource(KeyboardCursor::default());
+}
 
-/// Setup plugin resources
-fn setup(mut commands: Commands) {
-    commands.insert_resource(ClickDelay(Timer::from_seconds(0.4, TimerMode::Once)));
+/// Spawn the [`KeyboardHighlight`], it is positioned by [`handle_keyboard_input`]
+fn spawn_keyboard_highlight(mut commands: Commands) {
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(0.2, 0.6, 1.0, 0.4),
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        KeyboardHighlight,
+    ));
 }
 
-/// Update time of the [`ClickDelay`] resource
-fn update_time(time: Res<Time<Real>>, mut delay: ResMut<ClickDelay>) {
-    delay.0.tick(time.delta());
+/// Remember that the pause state is left, see [`InputPlugin::with_pause`]
+fn mark_resumed(mut commands: Commands) {
+    commands.insert_resource(Resumed);
+}
+
+/// Remember that the countdown state is left, see [`InputPlugin::with_countdown`]
+fn mark_counted_down(mut commands: Commands) {
+    commands.insert_resource(CountedDown);
+}
+
+/// Report gamepads being connected and disconnected, [`Gamepads`] keeps track of them
+fn log_gamepad_connections(mut events: EventReader<GamepadConnectionEvent>) {
+    for event in events.read() {
+        match &event.connection {
+            GamepadConnection::Connected(info) => {
+                info!("Gamepad {} connected: {}", event.gamepad.id, info.name)
+            }
+            GamepadConnection::Disconnected => {
+                info!("Gamepad {} disconnected", event.gamepad.id)
+            }
+        }
+    }
+}
+
+/// Update time of the [`ClickDelay`] resource and send the click kept during the delay
+fn update_time(
+    time: Res<Time<Real>>,
+    mut delay: ResMut<ClickDelay>,
+    mut events: EventWriter<ClickEvent>,
+) {
+    if delay.timer.tick(time.delta()).just_finished() {
+        if let Some(click) = delay.pending.take() {
+            events.send(click);
+        }
+    }
 }
 
 /// Convert a world position to an [`ClickEvent`] if the click is inside the region
@@ -71,83 +373,194 @@ fn to_tile_pos(grid: &Grid, world_pos: Vec2) -> Option<ClickEvent> {
     let field_width = grid.field.1.x - grid.field.0.x;
     let field_height = grid.field.1.y - grid.field.0.y;
     let field_size = Vec2::new(field_width, field_height);
-    let tile_size = field_size / grid.size.as_vec2();
+    let tile_size = tile_size(grid);
     let top_offset = grid.field.0.y;
 
     // Shift position up such that all coordinates are positive and
     // the top region is negative
     let shifted = world_pos + field_size / 2.0 - Vec2::new(0.0, top_offset / 2.0);
-    let scaled = shifted / tile_size;
+    let scaled = (shifted - grid.inset) / tile_size;
     if scaled.y >= grid.size.y as f32 || scaled.y < 0.0 {
         return None;
     }
+    if scaled.x >= grid.size.x as f32 || scaled.x < 0.0 {
+        return None;
+    }
     Some(ClickEvent {
         tile_x: scaled.x.trunc() as usize,
         tile_y: scaled.y.trunc() as usize,
     })
 }
 
+/// Size of a single tile in world units
+fn tile_size(grid: &Grid) -> Vec2 {
+    let field_size = grid.field.1 - grid.field.0;
+    (field_size - 2.0 * grid.inset) / grid.size.as_vec2()
+}
+
+/// World position of the center of a tile, the inverse of [`to_tile_pos`]
+fn to_world_pos(grid: &Grid, tile: UVec2) -> Vec2 {
+    let field_size = grid.field.1 - grid.field.0;
+    let top_offset = grid.field.0.y;
+    let scaled = tile.as_vec2() + 0.5;
+    let shifted = scaled * tile_size(grid) + grid.inset;
+    let world_pos = shifted - field_size / 2.0 + Vec2::new(0.0, top_offset / 2.0);
+    world_pos * Vec2::new(1.0, -1.0)
+}
+
+/// Convert a position on the screen in logical pixels to ndc [-1..1] (gpu coordinates)
+fn screen_to_ndc(win: &Window, position: Vec2) -> Vec2 {
+    let window_size = Vec2::new(win.width(), win.height());
+    let ndc = (position / window_size) * 2.0 - Vec2::ONE;
+    // Flip vertical
+    Vec2::new(1.0, -1.0) * ndc
+}
+
+/// Convert a position on the screen in logical pixels to world coordinates with the y axis
+/// pointing down, as used by [`to_tile_pos`]
+fn screen_to_world(
+    win: &Window,
+    projection: Mat4,
+    camera_transform: &GlobalTransform,
+    position: Vec2,
+) -> Vec2 {
+    let ndc = screen_to_ndc(win, position);
+
+    // matrix for undoing the projection and camera transform
+    let ndc_to_world = camera_transform.compute_matrix() * projection.inverse();
+
+    // use it to convert ndc to world-space coordinates
+    let world_pos = ndc_to_world.project_point3(ndc.extend(-1.0));
+
+    // Flip Y axis and reduce to 2d vector
+    world_pos.truncate() * Vec2::new(1.0, -1.0)
+}
+
+/// Scale the view to the largest size at which the laid out window fits into the actual one.
+/// The world coordinates stay the same, the field is only centered and scaled, so it stays
+/// square and clicks keep mapping to the same tiles.
+fn fit_to_window(
+    mut resized: EventReader<WindowResized>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut projections: Query<&mut OrthographicProjection>,
+    added: Query<(), Added<OrthographicProjection>>,
+    grid: Res<Grid>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    // Every screen spawns its own camera, which has to be scaled as well
+    let resized = resized.read().count() > 0;
+    if !resized && added.is_empty() {
+        return;
+    }
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let layout = grid.field.1;
+    let scale = (window.width() / layout.x).min(window.height() / layout.y);
+    if scale <= 0.0 {
+        // Minimized
+        return;
+    }
+    for mut projection in &mut projections {
+        projection.scale = 1.0 / scale;
+    }
+    ui_scale.0 = scale;
+}
+
 /// Handle mouse clicks
 /// Convert screen position to a tile position
+#[allow(clippy::too_many_arguments)]
 fn handle_click_input(
     mouse_btn: Res<ButtonInput<MouseButton>>,
     windows: Query<&Window, With<PrimaryWindow>>,
     q_camera: Query<(&Camera, &GlobalTransform)>,
     mut event: EventWriter<ClickEvent>,
+    (debug, mut raw_event): (Res<DebugInput>, EventWriter<RawClickEvent>),
     settings: Res<Grid>,
-    start_delay: Res<ClickDelay>,
+    mut start_delay: ResMut<ClickDelay>,
+    dedup: Res<InputDedup>,
+    last_touch: Res<LastTouch>,
+    time: Res<Time<Real>>,
 ) {
-    if !start_delay.0.finished() {
-        return;
-    }
     let Ok(win) = windows.get_single() else {
         panic!("No primary window found");
     };
-    if mouse_btn.just_pressed(MouseButton::Left) {
+    // The click is most likely emulated from a touch which was already handled
+    let after_touch = last_touch
+        .0
+        .is_some_and(|touch| time.elapsed().saturating_sub(touch) < dedup.window);
+    if mouse_btn.just_pressed(MouseButton::Left) && !after_touch {
         let Some(pos) = win.cursor_position() else {
             return;
         };
         let (camera, camera_transform) = q_camera.single();
-
-        let width = win.width();
-        let height = win.height();
-
-        let window_size = Vec2::new(width, height);
-
-        // convert screen position [0..resolution] to ndc [-1..1] (gpu coordinates)
-        let ndc = (pos / window_size) * 2.0 - Vec2::ONE;
-        // Flip vertical
-        let ndc = Vec2::new(1.0, -1.0) * ndc;
-
-        // matrix for undoing the projection and camera transform
-        let ndc_to_world = camera_transform.compute_matrix() * camera.projection_matrix().inverse();
-
-        // use it to convert ndc to world-space coordinates
-        let world_pos = ndc_to_world.project_point3(ndc.extend(-1.0));
-
-        // Flip Y axis and reduce to 2d vector
-        let world_pos = world_pos.truncate() * Vec2::new(1.0, -1.0);
-
+        let world_pos = screen_to_world(win, camera.projection_matrix(), camera_transform, pos);
+        if debug.0 {
+            raw_event.send(RawClickEvent {
+                position: pos,
+                world_pos: world_pos * Vec2::new(1.0, -1.0),
+            });
+        }
         if let Some(tile) = to_tile_pos(&settings, world_pos) {
-            event.send(tile);
+            start_delay.submit(tile, &mut event);
         }
     }
 }
 
+/// Move the [`KeyboardCursor`] with the bound keys or a gamepad and click its tile with
+/// [`Action::Confirm`] or the south button of the gamepad
+#[allow(clippy::too_many_arguments)]
+fn handle_keyboard_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut gamepad: GamepadInput,
+    mut cursor: ResMut<KeyboardCursor>,
+    mut highlight: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<KeyboardHighlight>>,
+    mut event: EventWriter<ClickEvent>,
+    settings: Res<Grid>,
+    mut start_delay: ResMut<ClickDelay>,
+) {
+    let mut direction = gamepad.direction();
+    let pressed = |action| bindings.just_pressed(&keys, action) as i32;
+    direction.x += pressed(Action::MoveRight) - pressed(Action::MoveLeft);
+    direction.y += pressed(Action::MoveDown) - pressed(Action::MoveUp);
+    let confirm = gamepad.confirm() || bindings.just_pressed(&keys, Action::Confirm);
+    if direction == IVec2::ZERO && !confirm {
+        return;
+    }
+    // The grid size can change between sessions
+    let max = settings.size.saturating_sub(UVec2::ONE).as_ivec2();
+    let pos = (cursor.0.as_ivec2() + direction).clamp(IVec2::ZERO, max);
+    cursor.0 = pos.as_uvec2();
+    if confirm {
+        let click = ClickEvent {
+            tile_x: pos.x as usize,
+            tile_y: pos.y as usize,
+        };
+        start_delay.submit(click, &mut event);
+    }
+    for (mut transform, mut sprite, mut visibility) in &mut highlight {
+        transform.translation = to_world_pos(&settings, cursor.0).extend(3.0);
+        sprite.custom_size = Some(tile_size(&settings));
+        *visibility = Visibility::Visible;
+    }
+}
+
 /// Handle touch inputs
 /// Convert screen position to a tile position
 /// TODO: Does this work? It does not work in WASM
+#[allow(clippy::too_many_arguments)]
 fn handle_touch_input(
     mut touches: EventReader<TouchInput>,
     windows: Query<&Window, With<PrimaryWindow>>,
     q_camera: Query<(&Camera, &GlobalTransform)>,
     mut click_event: EventWriter<ClickEvent>,
+    (debug, mut raw_event): (Res<DebugInput>, EventWriter<RawClickEvent>),
     settings: Res<Grid>,
-    start_delay: Res<ClickDelay>,
+    mut start_delay: ResMut<ClickDelay>,
+    mut last_touch: ResMut<LastTouch>,
+    time: Res<Time<Real>>,
 ) {
-    if !start_delay.0.finished() {
-        return;
-    }
     let Ok(win) = windows.get_single() else {
         panic!("No primary window found");
     };
@@ -156,34 +569,367 @@ fn handle_touch_input(
             phase, position, ..
         } = event;
         info!("Touch registered");
+        // The touch can start and end in the same frame
         match phase {
             TouchPhase::Ended => (),
-            _ => return,
+            _ => continue,
         }
 
         let (camera, camera_transform) = q_camera.single();
+        let world_pos =
+            screen_to_world(win, camera.projection_matrix(), camera_transform, *position);
+        last_touch.0 = Some(time.elapsed());
+        if debug.0 {
+            raw_event.send(RawClickEvent {
+                position: *position,
+                world_pos: world_pos * Vec2::new(1.0, -1.0),
+            });
+        }
+        if let Some(tile) = to_tile_pos(&settings, world_pos) {
+            start_delay.submit(tile, &mut click_event);
+        }
+    }
+}
 
-        let width = win.width();
-        let height = win.height();
+/// Log every [`RawClickEvent`] and move the [`ClickMarker`] to the last one
+fn show_click_marker(
+    mut commands: Commands,
+    mut events: EventReader<RawClickEvent>,
+    mut markers: Query<&mut Transform, With<ClickMarker>>,
+) {
+    let mut last = None;
+    for event in events.read() {
+        info!(
+            "Click at {} on the screen, {} in the world",
+            event.position, event.world_pos
+        );
+        last = Some(event.world_pos);
+    }
+    let Some(world_pos) = last else {
+        return;
+    };
+    let translation = world_pos.extend(5.0);
+    if let Ok(mut transform) = markers.get_single_mut() {
+        transform.translation = translation;
+        return;
+    }
+    let color = Color::rgb(1.0, 0.0, 1.0);
+    let marker = commands
+        .spawn((
+            SpatialBundle::from_transform(Transform::from_translation(translation)),
+            ClickMarker,
+        ))
+        .id();
+    for size in [Vec2::new(12.0, 2.0), Vec2::new(2.0, 12.0)] {
+        commands
+            .spawn(SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(size),
+                    ..default()
+                },
+                ..default()
+            })
+            .set_parent(marker);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{RunningState, FIELD_SIZE_X, FIELD_SIZE_Y, SCORE_HEIGHT};
+    use bevy::input::mouse::MouseButtonInput;
+    use bevy::input::ButtonState;
+    use bevy::render::camera::CameraProjection;
+    use bevy::time::TimeUpdateStrategy;
+    use bevy::window::WindowResolution;
+
+    /// Grid of the game below the score bar, with `inset`.
+    /// In world coordinates with y pointing down the field spans from `-FIELD_SIZE_X / 2.0` to
+    /// `FIELD_SIZE_X / 2.0` and from `-(FIELD_SIZE_Y - SCORE_HEIGHT) / 2.0` to
+    /// `(FIELD_SIZE_Y + SCORE_HEIGHT) / 2.0`.
+    fn grid(inset: f32) -> Grid {
+        Grid {
+            size: UVec2::new(5, 5),
+            field: (
+                Vec2::new(0.0, SCORE_HEIGHT),
+                Vec2::new(FIELD_SIZE_X, FIELD_SIZE_Y + SCORE_HEIGHT),
+            ),
+            inset,
+        }
+    }
+
+    /// Left edge of the field in world coordinates
+    const LEFT: f32 = -FIELD_SIZE_X / 2.0;
+    /// Top edge of the field in world coordinates, y pointing down
+    const TOP: f32 = -(FIELD_SIZE_Y - SCORE_HEIGHT) / 2.0;
+    /// Size of a tile without inset
+    const TILE: f32 = FIELD_SIZE_X / 5.0;
+
+    fn tile(grid: &Grid, x: f32, y: f32) -> Option<(usize, usize)> {
+        to_tile_pos(grid, Vec2::new(x, y)).map(|click| (click.tile_x, click.tile_y))
+    }
 
-        let window_size = Vec2::new(width, height);
+    #[test]
+    fn score_bar_is_not_a_tile() {
+        let grid = grid(0.0);
+        assert_eq!(tile(&grid, 0.0, TOP - SCORE_HEIGHT / 2.0), None);
+        assert_eq!(tile(&grid, 0.0, TOP - 0.1), None);
+    }
 
-        // convert screen position [0..resolution] to ndc [-1..1] (gpu coordinates)
-        let ndc = (*position / window_size) * 2.0 - Vec2::ONE;
-        // Flip vertical
-        let ndc = Vec2::new(1.0, -1.0) * ndc;
+    #[test]
+    fn tile_boundaries() {
+        let grid = grid(0.0);
+        assert_eq!(tile(&grid, LEFT, TOP), Some((0, 0)));
+        assert_eq!(
+            tile(&grid, LEFT + TILE - 0.1, TOP + TILE - 0.1),
+            Some((0, 0))
+        );
+        assert_eq!(tile(&grid, LEFT + TILE, TOP + TILE), Some((1, 1)));
+        assert_eq!(
+            tile(&grid, LEFT + 3.0 * TILE, TOP + 2.0 * TILE),
+            Some((3, 2))
+        );
+    }
 
-        // matrix for undoing the projection and camera transform
-        let ndc_to_world = camera_transform.compute_matrix() * camera.projection_matrix().inverse();
+    #[test]
+    fn right_and_bottom_edges() {
+        let grid = grid(0.0);
+        let right = LEFT + FIELD_SIZE_X;
+        let bottom = TOP + FIELD_SIZE_Y;
+        assert_eq!(tile(&grid, right - 0.1, bottom - 0.1), Some((4, 4)));
+        assert_eq!(tile(&grid, right, 0.0), None);
+        assert_eq!(tile(&grid, 0.0, bottom), None);
+    }
+
+    #[test]
+    fn outside_the_field() {
+        let grid = grid(0.0);
+        assert_eq!(tile(&grid, LEFT - 0.1, 0.0), None);
+        assert_eq!(tile(&grid, -1000.0, -1000.0), None);
+        assert_eq!(tile(&grid, 1000.0, 1000.0), None);
+    }
+
+    #[test]
+    fn inset_margin_is_not_a_tile() {
+        const INSET: f32 = 10.0;
+        let grid = grid(INSET);
+        let right = LEFT + FIELD_SIZE_X;
+        let bottom = TOP + FIELD_SIZE_Y;
+        assert_eq!(tile(&grid, LEFT + INSET / 2.0, 0.0), None);
+        assert_eq!(tile(&grid, right - INSET / 2.0, 0.0), None);
+        assert_eq!(tile(&grid, 0.0, TOP + INSET / 2.0), None);
+        assert_eq!(tile(&grid, 0.0, bottom - INSET / 2.0), None);
+        // The tiles are divided over the remaining area
+        assert_eq!(tile(&grid, LEFT + INSET, TOP + INSET), Some((0, 0)));
+        assert_eq!(
+            tile(&grid, right - INSET - 0.1, bottom - INSET - 0.1),
+            Some((4, 4))
+        );
+    }
+
+    #[test]
+    fn tile_centers_round_trip() {
+        for grid in [grid(0.0), grid(10.0)] {
+            for pos in [UVec2::new(0, 0), UVec2::new(2, 3), UVec2::new(4, 4)] {
+                // to_world_pos points y up like the world
+                let center = to_world_pos(&grid, pos) * Vec2::new(1.0, -1.0);
+                assert_eq!(
+                    tile(&grid, center.x, center.y),
+                    Some((pos.x as usize, pos.y as usize))
+                );
+            }
+        }
+    }
+
+    /// Tiles of all [`ClickEvent`]s
+    #[derive(Debug, Default, Resource)]
+    struct Received(Vec<(usize, usize)>);
+
+    fn collect_clicks(mut clicks: EventReader<ClickEvent>, mut received: ResMut<Received>) {
+        received
+            .0
+            .extend(clicks.read().map(|click| (click.tile_x, click.tile_y)));
+    }
 
-        // use it to convert ndc to world-space coordinates
-        let world_pos = ndc_to_world.project_point3(ndc.extend(-1.0));
+    /// Submit a click in the first frame only
+    fn submit_once(
+        mut delay: ResMut<ClickDelay>,
+        mut events: EventWriter<ClickEvent>,
+        mut done: Local<bool>,
+    ) {
+        if !std::mem::replace(&mut *done, true) {
+            let click = ClickEvent {
+                tile_x: 1,
+                tile_y: 2,
+            };
+            delay.submit(click, &mut events);
+        }
+    }
+
+    #[test]
+    fn click_during_delay_is_sent_afterwards() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                100,
+            )))
+            .insert_resource(ClickDelay::new(0.4))
+            .add_event::<ClickEvent>()
+            .init_resource::<Received>()
+            .add_systems(PreUpdate, update_time)
+            .add_systems(Update, (submit_once, collect_clicks).chain());
+        app.update();
+        app.update();
+        assert!(app.world.resource::<Received>().0.is_empty());
+        for _ in 0..5 {
+            app.update();
+        }
+        assert_eq!(app.world.resource::<Received>().0, [(1, 2)]);
+    }
+
+    #[test]
+    fn touch_on_scaled_window() {
+        // 300 by 300 logical pixels at a scale factor of 2.0, touches arrive in logical pixels
+        let mut resolution = WindowResolution::default().with_scale_factor_override(2.0);
+        resolution.set_physical_resolution(600, 600);
+        let win = Window {
+            resolution,
+            ..default()
+        };
+        assert_eq!(win.width(), 300.0);
+        assert_eq!(screen_to_ndc(&win, Vec2::new(150.0, 150.0)), Vec2::ZERO);
+        assert_eq!(
+            screen_to_ndc(&win, Vec2::new(300.0, 300.0)),
+            Vec2::new(1.0, -1.0)
+        );
+    }
+
+    /// App taking input in [`RunningState::Running`] on a window of the game, returns the window
+    fn input_app() -> (App, Entity) {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, bevy::input::InputPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+                16,
+            )))
+            .init_state::<RunningState>()
+            .init_resource::<KeyBindings>()
+            .init_resource::<Received>()
+            .add_event::<WindowResized>()
+            .add_plugins(
+                InputPlugin::new(
+                    RunningState::Running,
+                    UVec2::new(5, 5),
+                    Vec2::new(0.0, SCORE_HEIGHT),
+                    Vec2::new(FIELD_SIZE_X, FIELD_SIZE_Y + SCORE_HEIGHT),
+                )
+                .with_countdown(RunningState::Countdown),
+            )
+            .add_systems(Last, collect_clicks);
+        let resolution = WindowResolution::new(FIELD_SIZE_X, FIELD_SIZE_Y + SCORE_HEIGHT);
+        let window = Window {
+            resolution,
+            ..default()
+        };
+        let window = app.world.spawn((window, PrimaryWindow)).id();
+        // Without a renderer the projection is the identity instead of the 2d camera's. The center
+        // of the window is the center of the world either way.
+        app.world
+            .spawn((Camera::default(), GlobalTransform::default()));
+        for state in [RunningState::Countdown, RunningState::Running] {
+            app.world
+                .resource_mut::<NextState<RunningState>>()
+                .set(state);
+            app.update();
+        }
+        // Let the ClickDelay after the countdown finish
+        app.update();
+        (app, window)
+    }
+
+    fn mouse(window: Entity, state: ButtonState) -> MouseButtonInput {
+        MouseButtonInput {
+            button: MouseButton::Left,
+            state,
+            window,
+        }
+    }
+
+    #[test]
+    fn tap_reported_as_touch_and_click_counts_once() {
+        let (mut app, window) = input_app();
+        let center = Vec2::new(FIELD_SIZE_X, FIELD_SIZE_Y + SCORE_HEIGHT) / 2.0;
+        app.world
+            .get_mut::<Window>(window)
+            .unwrap()
+            .set_cursor_position(Some(center));
+        for phase in [TouchPhase::Started, TouchPhase::Ended] {
+            app.world.send_event(TouchInput {
+                phase,
+                position: center,
+                window,
+                force: None,
+                id: 0,
+            });
+        }
+        app.world.send_event(mouse(window, ButtonState::Pressed));
+        app.update();
+        assert_eq!(app.world.resource::<Received>().0, [(2, 2)]);
+
+        // Clicks after the InputDedup::window count again
+        app.world.send_event(mouse(window, ButtonState::Released));
+        for _ in 0..40 {
+            app.update();
+        }
+        app.world.send_event(mouse(window, ButtonState::Pressed));
+        app.update();
+        assert_eq!(app.world.resource::<Received>().0, [(2, 2), (2, 2)]);
+    }
 
-        // Flip Y axis and reduce to 2d vector
-        let world_pos = world_pos.truncate() * Vec2::new(1.0, -1.0);
+    #[test]
+    fn tiles_stay_in_place_after_resize() {
+        let (mut app, window) = input_app();
+        let camera = app.world.spawn(OrthographicProjection::default()).id();
+        let grid = grid(0.0);
+        let layout = grid.field.1;
+        for (width, height) in [(900.0, 400.0), (300.0, 1000.0), (1600.0, 1600.0)] {
+            app.world
+                .get_mut::<Window>(window)
+                .unwrap()
+                .resolution
+                .set(width, height);
+            app.world.send_event(WindowResized {
+                window,
+                width,
+                height,
+            });
+            app.update();
 
-        if let Some(tile) = to_tile_pos(&settings, dbg!(world_pos)) {
-            click_event.send(tile);
+            let win = app.world.get::<Window>(window).unwrap().clone();
+            let mut projection = app
+                .world
+                .get::<OrthographicProjection>(camera)
+                .unwrap()
+                .clone();
+            projection.update(width, height);
+            // Where the laid out window ends up, centered and scaled to fit
+            let scale = (width / layout.x).min(height / layout.y);
+            let window_center = Vec2::new(width, height) / 2.0;
+            for x in 0..5 {
+                for y in 0..5 {
+                    let laid_out =
+                        grid.field.0 + (Vec2::new(x as f32, y as f32) + 0.5) * tile_size(&grid);
+                    let position = window_center + (laid_out - layout / 2.0) * scale;
+                    let world_pos = screen_to_world(
+                        &win,
+                        projection.get_projection_matrix(),
+                        &GlobalTransform::default(),
+                        position,
+                    );
+                    let click = to_tile_pos(&grid, world_pos).unwrap();
+                    assert_eq!((click.tile_x, click.tile_y), (x, y));
+                }
+            }
         }
     }
 }
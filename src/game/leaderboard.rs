@@ -0,0 +1,151 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{ClickIntervals, GameMode, GameOverReason, ReactionBounds, RunningState, Score};
+use crate::storage::Storage;
+
+/// Plugin keeping the best scores of all sessions
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        let leaderboard: Leaderboard = Storage::of(app).load(Leaderboard::KEY).unwrap_or_default();
+        app.insert_resource(leaderboard)
+            .add_systems(OnEnter(RunningState::Finished), record_leaderboard);
+    }
+}
+
+/// A finished session on the [`Leaderboard`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub score: usize,
+    pub mode: GameMode,
+    /// Whether the target score was reached, `None` outside of [`GameMode::Target`]
+    #[serde(default)]
+    pub target_met: Option<bool>,
+    /// Day the session was played as `YYYY-MM-DD`
+    pub date: String,
+}
+
+/// Best sessions of all games ever played sorted by score, persisted between runs
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Resource)]
+pub struct Leaderboard(pub Vec<LeaderboardEntry>);
+
+impl Leaderboard {
+    /// Storage key
+    const KEY: &'static str = "leaderboard";
+    /// Number of entries which are kept
+    const SIZE: usize = 10;
+
+    /// Insert an entry if it is good enough, earlier entries stay ahead on equal scores.
+    /// Returns the rank of the entry starting at 0.
+    fn insert(&mut self, entry: LeaderboardEntry) -> Option<usize> {
+        let rank = self
+            .0
+            .iter()
+            .position(|e| e.score < entry.score)
+            .unwrap_or(self.0.len());
+        if rank >= Self::SIZE {
+            return None;
+        }
+        self.0.insert(rank, entry);
+        self.0.truncate(Self::SIZE);
+        Some(rank)
+    }
+}
+
+/// Add the score of the finished session and store the result
+fn record_leaderboard(
+    mut leaderboard: ResMut<Leaderboard>,
+    score: Res<Score>,
+    mode: Res<GameMode>,
+    reason: Res<GameOverReason>,
+    intervals: Res<ClickIntervals>,
+    bounds: Res<ReactionBounds>,
+    storage: Res<Storage>,
+) {
+    if score.0 == 0 || *mode == GameMode::Zen {
+        return;
+    }
+    if intervals.suspect {
+        info!(
+            "Assisted session with score {} left off the leaderboard, hits came within {:?}",
+            score.0, bounds.min
+        );
+        return;
+    }
+    let entry = LeaderboardEntry {
+        score: score.0,
+        mode: *mode,
+        target_met: (*mode == GameMode::Target).then_some(*reason == GameOverReason::TargetReached),
+        date: today(),
+    };
+    if let Some(rank) = leaderboard.insert(entry) {
+        info!(
+            "Score {} is number {} on the leaderboard",
+            score.0,
+            rank + 1
+        );
+        storage.save(Leaderboard::KEY, &*leaderboard);
+    }
+}
+
+/// Current date in UTC as `YYYY-MM-DD`
+#[cfg(not(target_arch = "wasm32"))]
+fn today() -> String {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    // Days since the epoch to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = (secs / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Current date in UTC as `YYYY-MM-DD`
+#[cfg(target_arch = "wasm32")]
+fn today() -> String {
+    let date = js_sys::Date::new_0();
+    format!(
+        "{:04}-{:02}-{:02}",
+        date.get_utc_full_year(),
+        date.get_utc_month() + 1,
+        date.get_utc_date()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{headless_app, start_session, Difficulty, GameOverEvent};
+
+    /// Leaderboard after finishing a session of `mode` with a score of 10 for `reason`
+    fn finish(mode: GameMode, reason: GameOverReason) -> Leaderboard {
+        let mut app = headless_app(Difficulty::Normal);
+        app.insert_resource(mode);
+        start_session(&mut app, RunningState::Running, None);
+        app.world.resource_mut::<Score>().0 = 10;
+        app.world.send_event(GameOverEvent(reason));
+        app.update();
+        app.update();
+        app.world.resource::<Leaderboard>().clone()
+    }
+
+    #[test]
+    fn target_met_is_recorded() {
+        let reached = finish(GameMode::Target, GameOverReason::TargetReached);
+        assert_eq!(reached.0[0].target_met, Some(true));
+        let missed = finish(GameMode::Target, GameOverReason::TimeUp);
+        assert_eq!(missed.0[0].target_met, Some(false));
+        let timed = finish(GameMode::Timed, GameOverReason::TimeUp);
+        assert_eq!(timed.0[0].target_met, None);
+    }
+}
@@ -2,6 +2,7 @@ use bevy::prelude::*;
 
 use super::GameState;
 use crate::despawn_on_screen;
+use crate::settings::{FontChoice, KeyBindings, Palette, Settings, SettingsBackend};
 
 /// Plugin for loading assets
 pub struct LoadingPlugin;
@@ -9,27 +10,46 @@ pub struct LoadingPlugin;
 impl Plugin for LoadingPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<AssetsLoading>()
+            .init_resource::<SettingsBackend>()
             .add_systems(OnEnter(GameState::Loading), splash_setup)
             .add_systems(
                 Update,
-                check_assets_ready.run_if(in_state(GameState::Loading)),
+                (check_assets_ready, fade_splash_logo)
+                    .chain()
+                    .run_if(in_state(GameState::Loading)),
             )
             .add_systems(
                 OnExit(GameState::Loading),
                 despawn_on_screen::<OnSplashScreen>,
+            )
+            .add_systems(
+                Update,
+                (swap_font, fall_back_font)
+                    .chain()
+                    .run_if(resource_exists::<Assets>.and_then(not(in_state(GameState::Loading)))),
             );
     }
 }
 
 /// Tag for marking entities belonging to the splash screen
-/// TODO: add logo/icon during startup to not have this being useless
 #[derive(Component)]
 struct OnSplashScreen;
 
+/// Logo shown on the splash screen, faded out over the [`SplashTimer`]
+#[derive(Component)]
+struct SplashLogo;
+
 /// List of assets to track the loading state
 #[derive(Debug, Default, Resource)]
 pub struct AssetsLoading(pub Vec<UntypedHandle>);
 
+/// Paths of the assets which failed to load, shown in [`GameState::LoadError`]
+#[derive(Debug, Default, Resource)]
+pub struct LoadErrors(pub Vec<String>);
+
+/// Samples played in turn for a hit, more files can simply be added
+const HIT_SAMPLES: &[&str] = &["test.wav"];
+
 /// Timer to specify minimum splash screen display time
 #[derive(Resource)]
 struct SplashTimer(Timer);
@@ -39,71 +59,147 @@ struct SplashTimer(Timer);
 #[derive(Resource)]
 pub struct Assets {
     pub font: Handle<Font>,
-    pub hit: Handle<AudioSource>,
+    /// Samples for a hit, see [`HIT_SAMPLES`]
+    pub hit: Vec<Handle<AudioSource>>,
     pub error: Handle<AudioSource>,
+    pub spawn: Handle<AudioSource>,
+    pub tick: Handle<AudioSource>,
+    pub bonus: Handle<AudioSource>,
+    pub time_bonus: Handle<AudioSource>,
+    pub warning: Handle<AudioSource>,
 }
 
-/// Setup splash screen and start loading assets
+/// Setup splash screen, load the stored settings and start loading assets
 fn splash_setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut loading: ResMut<AssetsLoading>,
+    mut palette: ResMut<Palette>,
+    backend: Res<SettingsBackend>,
 ) {
-    //let icon = asset_server.load("bevy_icon.png");
-
-    //commands
-    //    .spawn((
-    //        NodeBundle {
-    //            style: Style {
-    //                align_items: AlignItems::Center,
-    //                justify_content: JustifyContent::Center,
-    //                width: Val::Percent(100.0),
-    //                height: Val::Percent(100.0),
-    //                ..default()
-    //            },
-    //            ..default()
-    //        },
-    //        OnSplashScreen,
-    //    ))
-    //    .with_children(|parent| {
-    //        parent.spawn(ImageBundle {
-    //            style: Style {
-    //                // This will set the logo to be 200px wide, and auto adjust its height
-    //                width: Val::Px(200.0),
-    //                ..default()
-    //            },
-    //            image: UiImage::new(icon),
-    //            ..default()
-    //        });
-    //    });
+    // Entered again when retrying after a failure
+    loading.0.clear();
+    let logo = asset_server.load("logo.png");
+    loading.0.push(logo.clone().untyped());
+    commands.spawn((Camera2dBundle::default(), OnSplashScreen));
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                ..default()
+            },
+            OnSplashScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                ImageBundle {
+                    style: Style {
+                        // This will set the logo to be 200px wide, and auto adjust its height
+                        width: Val::Px(200.0),
+                        ..default()
+                    },
+                    image: UiImage::new(logo),
+                    ..default()
+                },
+                SplashLogo,
+            ));
+        });
+    let mut font_choice = FontChoice::default();
+    if let Some(settings) = backend.0.load() {
+        palette.set_scheme(settings.color_scheme);
+        font_choice = settings.font;
+        commands.insert_resource(settings.key_bindings.clone());
+        commands.insert_resource(settings.difficulty);
+        commands.insert_resource(settings);
+    }
     // Load assets
-    let font = asset_server.load("fonts/EBGaramond-Regular.ttf");
-    loading.0.push(font.clone().untyped());
-    let hit = asset_server.load("test.wav");
-    loading.0.push(hit.clone().untyped());
+    let font = load_font(&asset_server, font_choice);
+    // The built-in font is not loaded through the asset server
+    if font_choice.path().is_some() {
+        loading.0.push(font.clone().untyped());
+    }
+    let hit: Vec<Handle<AudioSource>> = HIT_SAMPLES
+        .iter()
+        .map(|path| asset_server.load(*path))
+        .collect();
+    loading
+        .0
+        .extend(hit.iter().map(|sample| sample.clone().untyped()));
     let error = asset_server.load("test2.wav");
     loading.0.push(error.clone().untyped());
-    let assets = Assets { font, hit, error };
+    let spawn = asset_server.load("spawn.wav");
+    loading.0.push(spawn.clone().untyped());
+    let tick = asset_server.load("tick.wav");
+    loading.0.push(tick.clone().untyped());
+    let bonus = asset_server.load("bonus.wav");
+    loading.0.push(bonus.clone().untyped());
+    let time_bonus = asset_server.load("time_bonus.wav");
+    loading.0.push(time_bonus.clone().untyped());
+    let warning = asset_server.load("warning.wav");
+    loading.0.push(warning.clone().untyped());
+    let assets = Assets {
+        font,
+        hit,
+        error,
+        spawn,
+        tick,
+        bonus,
+        time_bonus,
+        warning,
+    };
     commands.insert_resource(assets);
     commands.insert_resource(SplashTimer(Timer::from_seconds(1.0, TimerMode::Once)));
 }
 
-/// Check when the assets are ready and transition state
+/// Handle of a font, the built-in font is always available
+fn load_font(server: &AssetServer, choice: FontChoice) -> Handle<Font> {
+    choice.path().map_or_else(default, |path| server.load(path))
+}
+
+/// Check when the assets are ready and transition state, or show the failed assets.
+/// A font which failed to load is replaced by the built-in font, a missing logo is skipped.
+#[allow(clippy::too_many_arguments)]
 fn check_assets_ready(
     mut commands: Commands,
     server: Res<AssetServer>,
-    loading: Res<AssetsLoading>,
+    mut loading: ResMut<AssetsLoading>,
+    mut assets: ResMut<Assets>,
+    logos: Query<&UiImage, With<SplashLogo>>,
     mut game_state: ResMut<NextState<GameState>>,
     time: Res<Time>,
     mut timer: ResMut<SplashTimer>,
 ) {
     use bevy::asset::LoadState;
 
+    let font = assets.font.id().untyped();
+    let logos: Vec<_> = logos
+        .iter()
+        .map(|logo| logo.texture.id().untyped())
+        .collect();
+    loading.0.retain(|handle| {
+        if server.load_state(handle.id()) != LoadState::Failed {
+            return true;
+        }
+        if handle.id() == font {
+            warn!("Failed loading font, using the built-in font");
+            assets.font = default();
+            return false;
+        }
+        !logos.contains(&handle.id())
+    });
     let mut ready_count = 0;
+    let mut failed = Vec::new();
     for handle in loading.0.iter() {
         match server.load_state(handle.id()) {
             LoadState::Failed => {
-                println!("Failed loading asset {:?}", server.get_path(handle.id()));
+                let path = server.get_path(handle.id());
+                failed.push(path.map_or_else(|| format!("{:?}", handle.id()), |p| p.to_string()));
             }
             LoadState::Loaded => {
                 ready_count += 1;
@@ -114,6 +210,13 @@ fn check_assets_ready(
         }
     }
     timer.0.tick(time.delta());
+    // Give retried assets the time of the splash screen to be reloaded
+    if !failed.is_empty() && timer.0.finished() {
+        error!("Failed loading assets {:?}", failed);
+        commands.insert_resource(LoadErrors(failed));
+        game_state.set(GameState::LoadError);
+        return;
+    }
     if ready_count == loading.0.len() && timer.0.finished() {
         info!("Finished loading");
         commands.remove_resource::<AssetsLoading>();
@@ -121,3 +224,83 @@ fn check_assets_ready(
         game_state.set(GameState::Menu);
     }
 }
+
+/// Fade out the [`SplashLogo`] until the [`SplashTimer`] finishes,
+/// without the logo the splash screen is filled with the accent color instead
+fn fade_splash_logo(
+    server: Res<AssetServer>,
+    timer: Option<Res<SplashTimer>>,
+    palette: Res<Palette>,
+    mut logos: Query<(Entity, &UiImage, &mut Visibility, &Parent), With<SplashLogo>>,
+    mut backgrounds: Query<&mut BackgroundColor>,
+) {
+    use bevy::asset::LoadState;
+
+    let Some(timer) = timer else {
+        return;
+    };
+    for (entity, logo, mut visibility, parent) in &mut logos {
+        if server.load_state(&logo.texture) == LoadState::Failed {
+            if *visibility != Visibility::Hidden {
+                warn!("Failed loading the logo");
+                *visibility = Visibility::Hidden;
+                if let Ok(mut background) = backgrounds.get_mut(parent.get()) {
+                    *background = palette.button_normal().into();
+                }
+            }
+            continue;
+        }
+        // The background color of an image tints it
+        if let Ok(mut tint) = backgrounds.get_mut(entity) {
+            tint.0.set_a(timer.0.fraction_remaining());
+        }
+    }
+}
+
+/// Point all texts using `old` to the font `new`
+fn replace_font(texts: &mut Query<&mut Text>, old: &Handle<Font>, new: &Handle<Font>) {
+    for mut text in texts {
+        for section in text.sections.iter_mut() {
+            if section.style.font == *old {
+                section.style.font = new.clone();
+            }
+        }
+    }
+}
+
+/// Load the font selected in the [`Settings`] and switch all texts to it
+fn swap_font(
+    settings: Res<Settings>,
+    server: Res<AssetServer>,
+    mut assets: ResMut<Assets>,
+    mut texts: Query<&mut Text>,
+    mut current: Local<Option<FontChoice>>,
+) {
+    if !settings.is_changed() || *current == Some(settings.font) {
+        return;
+    }
+    // The font loaded during the splash screen already matches the settings
+    if current.replace(settings.font).is_none() {
+        return;
+    }
+    let font = load_font(&server, settings.font);
+    replace_font(&mut texts, &assets.font, &font);
+    assets.font = font;
+}
+
+/// Switch to the built-in font when the selected font failed to load
+fn fall_back_font(
+    server: Res<AssetServer>,
+    mut assets: ResMut<Assets>,
+    mut texts: Query<&mut Text>,
+) {
+    use bevy::asset::LoadState;
+
+    if server.load_state(&assets.font) != LoadState::Failed {
+        return;
+    }
+    warn!("Failed loading font, using the built-in font");
+    let font = Handle::default();
+    replace_font(&mut texts, &assets.font, &font);
+    assets.font = font;
+}
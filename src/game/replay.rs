@@ -0,0 +1,226 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::input::{ClickEvent, LiveInput};
+use super::{GameOverEvent, GameRng, RunningState, SESSION_STARTS};
+use crate::storage;
+
+/// Plugin recording the clicks of a session and exporting them as JSON at game over.
+/// With a [`ReplayPlayback`] the recorded clicks are played back instead of the live input.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        if let Some(playback) = ReplayPlayback::from_env() {
+            app.insert_resource(playback);
+        }
+        app.init_resource::<ReplayRecording>()
+            .init_resource::<Replay>()
+            .init_resource::<SessionTicks>()
+            .add_systems(
+                PostUpdate,
+                export_replay.run_if(in_state(RunningState::Running)),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    count_ticks,
+                    play_replay.run_if(resource_exists::<ReplayPlayback>),
+                )
+                    .chain()
+                    .before(super::click)
+                    .run_if(in_state(RunningState::Running)),
+            );
+        for start in SESSION_STARTS {
+            app.add_systems(start, (reset_replay, start_playback));
+        }
+    }
+}
+
+/// Configuration of the replay export, disabled by default
+#[derive(Debug, Resource)]
+pub struct ReplayRecording {
+    pub enabled: bool,
+    /// File the replay is written to, or the name of the download in the browser
+    pub path: String,
+}
+
+impl Default for ReplayRecording {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "replay.json".into(),
+        }
+    }
+}
+
+/// Steps of [`Time<Fixed>`] since the start of the current session.
+/// The whole session advances in these steps, so a click played back at the same tick meets the
+/// same field and scores the same points.
+#[derive(Debug, Default, Resource)]
+pub struct SessionTicks(pub u64);
+
+/// Handled clicks of the current session.
+/// Together with the seed of the [`GameRng`] and the session rules this describes the whole run.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Resource)]
+pub struct Replay {
+    /// Seed of the [`GameRng`], set when the session ends
+    pub seed: u64,
+    /// Clicks with the [`SessionTicks`] at which they were handled
+    pub events: Vec<(u64, ClickEvent)>,
+}
+
+impl Replay {
+    /// Record a click handled at `tick`
+    pub fn record(&mut self, tick: u64, event: &ClickEvent) {
+        self.events.push((tick, event.clone()));
+    }
+}
+
+/// A [`Replay`] which is played back in every session while this resource exists.
+/// The session is seeded with [`Replay::seed`] and the live input is disabled.
+/// The session rules, like the difficulty and mode, have to match the recorded session.
+#[derive(Debug, Resource)]
+pub struct ReplayPlayback {
+    replay: Replay,
+    /// Index of the next event to play
+    next: usize,
+}
+
+impl ReplayPlayback {
+    /// Environment variable with the path of a replay to play back
+    const ENV: &'static str = "TILE_CLICKER_REPLAY";
+
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, next: 0 }
+    }
+
+    /// Seed the session is played with
+    pub fn seed(&self) -> u64 {
+        self.replay.seed
+    }
+
+    /// Load the replay named by [`Self::ENV`], failures are logged and ignored
+    fn from_env() -> Option<Self> {
+        let path = std::env::var(Self::ENV).ok()?;
+        let replay = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|raw| serde_json::from_str(&raw).map_err(|e| e.to_string()));
+        match replay {
+            Ok(replay) => {
+                info!("Playing back replay {}", path);
+                Some(Self::new(replay))
+            }
+            Err(e) => {
+                warn!("Ignoring replay {}: {}", path, e);
+                None
+            }
+        }
+    }
+}
+
+/// Start each session with an empty replay at tick 0
+fn reset_replay(mut replay: ResMut<Replay>, mut ticks: ResMut<SessionTicks>) {
+    replay.events.clear();
+    ticks.0 = 0;
+}
+
+/// Count the fixed steps of the running session
+fn count_ticks(mut ticks: ResMut<SessionTicks>) {
+    ticks.0 += 1;
+}
+
+/// Play back from the first click and only take live input without a playback
+fn start_playback(playback: Option<ResMut<ReplayPlayback>>, mut live: ResMut<LiveInput>) {
+    live.0 = playback.is_none();
+    if let Some(mut playback) = playback {
+        playback.next = 0;
+    }
+}
+
+/// Send the recorded clicks which are due at the current [`SessionTicks`]
+fn play_replay(
+    mut playback: ResMut<ReplayPlayback>,
+    ticks: Res<SessionTicks>,
+    mut clicks: EventWriter<ClickEvent>,
+) {
+    let now = ticks.0;
+    let playback = &mut *playback;
+    while let Some((at, event)) = playback.replay.events.get(playback.next) {
+        if *at > now {
+            break;
+        }
+        clicks.send(event.clone());
+        playback.next += 1;
+    }
+}
+
+/// Complete the replay of the finished session and export it
+fn export_replay(
+    mut events: EventReader<GameOverEvent>,
+    mut replay: ResMut<Replay>,
+    rng: Res<GameRng>,
+    config: Res<ReplayRecording>,
+) {
+    if events.is_empty() {
+        return;
+    }
+    events.clear();
+    replay.seed = rng.seed;
+    if !config.enabled {
+        return;
+    }
+    match serde_json::to_string(&*replay) {
+        Ok(json) => storage::export(&config.path, &json),
+        Err(e) => warn!("Failed serializing the replay: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{
+        headless_app, start_session, Difficulty, GameOverReason, Grid, Score, SessionStats,
+    };
+
+    /// Headless app with a running session at `fps` frames per second, playing back `replay`
+    fn session_at(fps: u32, replay: Option<Replay>) -> App {
+        let mut app = headless_app(Difficulty::Normal);
+        if let Some(replay) = replay {
+            app.insert_resource(ReplayPlayback::new(replay));
+        }
+        start_session(&mut app, RunningState::Running, Some(fps));
+        app
+    }
+
+    #[test]
+    fn replay_at_other_frame_rate() {
+        let mut app = session_at(60, None);
+        for _ in 0..2000 {
+            if app.world.resource::<SessionStats>().hits == 5 {
+                break;
+            }
+            if let Some((pos, _)) = app.world.resource::<Grid>().most_urgent() {
+                app.world.send_event(ClickEvent {
+                    tile_x: pos.x as usize,
+                    tile_y: pos.y as usize,
+                });
+            }
+            app.update();
+        }
+        assert_eq!(app.world.resource::<SessionStats>().hits, 5);
+        let score = app.world.resource::<Score>().0;
+        app.world.send_event(GameOverEvent(GameOverReason::Stopped));
+        app.update();
+        let replay = app.world.resource::<Replay>().clone();
+        let last = replay.events.last().unwrap().0;
+
+        let mut app = session_at(144, Some(replay));
+        while app.world.resource::<SessionTicks>().0 < last {
+            app.update();
+        }
+        let stats = app.world.resource::<SessionStats>();
+        assert_eq!((stats.hits, stats.misses), (5, 0));
+        assert_eq!(app.world.resource::<Score>().0, score);
+    }
+}
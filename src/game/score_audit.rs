@@ -0,0 +1,119 @@
+use bevy::prelude::*;
+use bevy::utils::Duration;
+
+use super::{GameOverEvent, RunningState, Score, SESSION_STARTS};
+use crate::storage;
+
+/// Plugin recording how the score of a session came together, for debugging point totals
+pub struct ScoreAuditPlugin;
+
+impl Plugin for ScoreAuditPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScoreAudit>().add_systems(
+            PostUpdate,
+            report_score_audit.run_if(in_state(RunningState::Running)),
+        );
+        for start in SESSION_STARTS {
+            app.add_systems(start, reset_score_audit);
+        }
+    }
+}
+
+/// Cause of a score change
+#[derive(Debug, Clone, Copy)]
+pub enum ScoreSource {
+    /// A tile was clicked
+    Tile,
+    /// A tile lost all of its points, see [`super::TileExpiry::penalty`]
+    ExpiryPenalty,
+}
+
+/// One change of the score
+#[derive(Debug, Clone)]
+pub struct ScoreEntry {
+    /// Real time since the start of the session
+    pub time: Duration,
+    pub source: ScoreSource,
+    /// Points before any multiplier
+    pub base: usize,
+    /// Multiplier from [`super::SpeedScaledScoring`], 1 when disabled
+    pub speed: f32,
+    /// Multiplier from the combo and [`super::TileKind::Bonus`] tiles, 1 without either
+    pub combo: f32,
+    /// Score before the change
+    pub before: usize,
+    /// Score after the change
+    pub after: usize,
+}
+
+/// Every score change of the current session, only recorded when `enabled`
+#[derive(Debug, Default, Resource)]
+pub struct ScoreAudit {
+    pub enabled: bool,
+    /// Export the audit as CSV to this file at game over
+    pub export: Option<String>,
+    entries: Vec<ScoreEntry>,
+}
+
+impl ScoreAudit {
+    /// Record a score change, does nothing when disabled
+    pub fn record(&mut self, entry: ScoreEntry) {
+        if self.enabled {
+            self.entries.push(entry);
+        }
+    }
+
+    /// Render the audit as CSV including a header row
+    fn to_csv(&self) -> String {
+        let mut csv = String::from("time_ms,source,base,speed,combo,before,after\n");
+        for entry in &self.entries {
+            csv.push_str(&format!(
+                "{},{:?},{},{:.3},{:.1},{},{}\n",
+                entry.time.as_millis(),
+                entry.source,
+                entry.base,
+                entry.speed,
+                entry.combo,
+                entry.before,
+                entry.after
+            ));
+        }
+        csv
+    }
+}
+
+/// Start each session with an empty audit
+fn reset_score_audit(mut audit: ResMut<ScoreAudit>) {
+    audit.entries.clear();
+}
+
+/// Check the audit against the final score and export it
+fn report_score_audit(
+    mut events: EventReader<GameOverEvent>,
+    audit: Res<ScoreAudit>,
+    score: Res<Score>,
+) {
+    if events.is_empty() {
+        return;
+    }
+    events.clear();
+    if !audit.enabled {
+        return;
+    }
+    let audited = audit.entries.last().map_or(0, |entry| entry.after);
+    if audited == score.0 {
+        info!(
+            "Score audit: {} changes adding up to {}",
+            audit.entries.len(),
+            audited
+        );
+    } else {
+        warn!(
+            "Score audit mismatch: audited {} but the score is {}",
+            audited, score.0
+        );
+    }
+    if let Some(path) = &audit.export {
+        storage::export(path, &audit.to_csv());
+    }
+}
@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+use bevy::utils::Duration;
+use serde::{Deserialize, Serialize};
+
+use super::{GameOverEvent, GameTime, ReactionTimes, RunningState, SessionStats};
+use crate::storage::Storage;
+
+/// Plugin keeping track of statistics over all played games
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        let stats: LifetimeStats = Storage::of(app)
+            .load(LifetimeStats::KEY)
+            .unwrap_or_default();
+        app.insert_resource(stats)
+            .init_resource::<ReactionBounds>()
+            .add_systems(
+                PostUpdate,
+                record_lifetime_stats.run_if(in_state(RunningState::Running)),
+            );
+    }
+}
+
+/// Totals over all games ever played, persisted between runs
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Resource)]
+#[serde(default)]
+pub struct LifetimeStats {
+    /// Tiles clicked in time
+    pub tiles_cleared: u64,
+    /// Finished sessions
+    pub games_played: u64,
+    /// Real time spent in sessions
+    pub play_time: Duration,
+    /// Clicks on empty tiles, including the ones which didn't end the session
+    pub misses: u64,
+    /// Fastest plausible reaction time of all sessions
+    pub fastest_reaction: Option<Duration>,
+}
+
+impl LifetimeStats {
+    /// Storage key
+    const KEY: &'static str = "lifetime_stats";
+}
+
+/// Reaction times below `min` are physiologically implausible,
+/// they come from buffered clicks or automation and are not recorded.
+/// A streak of hits within `min` of each other keeps the session off the leaderboard, see
+/// [`super::ClickIntervals`].
+#[derive(Debug, Resource)]
+pub struct ReactionBounds {
+    pub min: Duration,
+}
+
+impl Default for ReactionBounds {
+    fn default() -> Self {
+        Self {
+            min: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Add the totals of the finished session and store the result
+fn record_lifetime_stats(
+    mut events: EventReader<GameOverEvent>,
+    mut stats: ResMut<LifetimeStats>,
+    time: Res<GameTime>,
+    reaction_times: Res<ReactionTimes>,
+    session: Res<SessionStats>,
+    bounds: Res<ReactionBounds>,
+    storage: Res<Storage>,
+) {
+    if events.is_empty() {
+        return;
+    }
+    events.clear();
+    stats.tiles_cleared = stats
+        .tiles_cleared
+        .saturating_add(reaction_times.0.len() as u64);
+    stats.games_played = stats.games_played.saturating_add(1);
+    stats.play_time = stats.play_time.saturating_add(time.0.elapsed());
+    stats.misses = stats.misses.saturating_add(session.misses as u64);
+    let (valid, flagged): (Vec<_>, Vec<_>) = reaction_times
+        .0
+        .iter()
+        .partition(|&&time| time >= bounds.min);
+    if !flagged.is_empty() {
+        warn!(
+            "Ignoring {} reaction times below {:?}: {:?}",
+            flagged.len(),
+            bounds.min,
+            flagged
+        );
+    }
+    if let Some(&fastest) = valid.into_iter().min() {
+        stats.fastest_reaction = Some(stats.fastest_reaction.map_or(fastest, |f| f.min(fastest)));
+    }
+    storage.save(LifetimeStats::KEY, &*stats);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{headless_app, start_session, Difficulty, GameOverReason};
+
+    #[test]
+    fn every_miss_is_counted() {
+        let mut app = headless_app(Difficulty::Normal);
+        start_session(&mut app, RunningState::Running, None);
+        app.world.resource_mut::<SessionStats>().misses = 3;
+        app.world.send_event(GameOverEvent(GameOverReason::TimeUp));
+        app.update();
+        let stats = app.world.resource::<LifetimeStats>();
+        assert_eq!(stats.misses, 3);
+        assert_eq!(stats.games_played, 1);
+    }
+}
@@ -0,0 +1,58 @@
+//! # Tile Clicker Game
+//! Try to click tiles when they spawn.
+//! The game lasts 30 seconds.
+//!
+//! A tile spawns after a timer reaches zero or if the only tile is clicked.
+//! The delay of the timer depends on the [`Difficulty`], see [`Difficulty::spawn_delay`].
+//! During the game the relative speed of [`bevy::time::Time<Virtual>`] ramps up along the
+//! [`game::SpeedCurve`] of the difficulty, which shortens the delay.
+//!
+//! Points are given when a tile is clicked in a timely manner.
+//! The longer it takes to click a tile the less points are received.
+//! How long a tile is worth points is given by the [`game::TileTiming`] of the difficulty and
+//! shrinks with the relative speed as well.
+//! The time is decreased in the fixed steps of [`bevy::time::Time<Fixed>`], which follows
+//! [`bevy::time::Time<Virtual>`] like the spawn timer, so the decay does not depend on the frame
+//! rate.
+//!
+//! The game is over after [`GAME_DURATION`] or when clicking on an empty tile, the other
+//! modes of the main menu are described by [`GameMode`].
+//!
+//! The game can be embedded into another Bevy app by adding [`TileClickerPlugins`] next to the
+//! `DefaultPlugins`. It expects a window of [`FIELD_SIZE_X`] by [`FIELD_SIZE_Y`] +
+//! [`SCORE_HEIGHT`] and the `assets` folder of this crate.
+
+use bevy::app::PluginGroupBuilder;
+use bevy::prelude::*;
+
+pub mod game;
+pub mod settings;
+pub mod storage;
+pub mod ui;
+
+pub use game::{
+    Difficulty, GameMode, GamePlugin, GameState, GridConfig, HighScore, LoadingPlugin,
+    RunningState, Score, FIELD_SIZE_X, FIELD_SIZE_Y, GAME_DURATION, SCORE_HEIGHT,
+};
+pub use settings::SettingsPlugin;
+pub use ui::UiPlugin;
+
+/// All plugins of the game, in the order they have to be added
+pub struct TileClickerPlugins;
+
+impl PluginGroup for TileClickerPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(SettingsPlugin)
+            .add(LoadingPlugin)
+            .add(UiPlugin)
+            .add(GamePlugin)
+    }
+}
+
+/// Generic system that takes a component as a parameter, and will despawn all entities with that component
+fn despawn_on_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
+    for entity in &to_despawn {
+        commands.entity(entity).despawn_recursive();
+    }
+}
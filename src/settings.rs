@@ -0,0 +1,554 @@
+//! # Settings
+//! Player preferences which are not part of the game rules.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game::Difficulty;
+use crate::storage;
+
+/// Plugin registering the settings and palette resources
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Settings>()
+            .init_resource::<Palette>()
+            .init_resource::<KeyBindings>();
+    }
+}
+
+/// Player preferences, persisted between runs
+#[derive(Debug, Clone, Serialize, Deserialize, Resource)]
+#[serde(default)]
+pub struct Settings {
+    /// Draw lines between the cells of the field
+    pub grid_lines: bool,
+    /// Show a histogram of the reaction times on the game over screen
+    pub reaction_histogram: bool,
+    /// Draw a short fading line from the previous tile to a newly spawned tile
+    pub spawn_trail: bool,
+    /// Require pressing the menu button on the game over screen twice
+    pub confirm_quit: bool,
+    /// Draw a dark outline behind text for readability
+    pub text_outline: bool,
+    /// Play a tick when a tile spawns, rising in pitch as the game speeds up
+    pub spawn_sound: bool,
+    /// Minimum scale of a tile before clicking it counts, clicks on smaller tiles are ignored
+    pub min_clickable_scale: f32,
+    /// Seconds the final board is shown before the game over menu appears
+    pub freeze_frame: f32,
+    /// How the empty cells of the field are colored
+    pub background_pattern: BackgroundPattern,
+    /// Show the points each tile is currently worth on it
+    pub tile_values: bool,
+    /// Draw a square on each tile which shrinks as the tile loses its points
+    pub decay_overlay: bool,
+    /// Color normal tiles from [`Palette::tile_fresh`] to [`Palette::tile_stale`] as they lose
+    /// their points, instead of [`Palette::tile`]
+    pub value_gradient: bool,
+    /// Tick and faintly flash the field shortly before the spawn timer spawns a tile
+    pub metronome: bool,
+    /// Volume of all sounds from 0 to 1
+    pub volume: f32,
+    /// Play no sounds at all, toggled during a session
+    pub muted: bool,
+    /// Vibrate on hits and misclicks, only supported in the browser on mobile devices
+    pub haptics: bool,
+    /// Colors of the field and tiles, see [`Palette::set_scheme`]
+    pub color_scheme: ColorScheme,
+    /// Font of all text
+    pub font: FontChoice,
+    /// Where new tiles appear relative to the previous one
+    pub spawn_pattern: SpawnPattern,
+    /// Keys of the keyboard controls, applied to the [`KeyBindings`] resource when loading
+    pub key_bindings: KeyBindings,
+    /// Difficulty selected in the main menu, applied to the [`Difficulty`] resource when loading
+    pub difficulty: Difficulty,
+}
+
+impl Settings {
+    /// Storage key
+    pub const KEY: &'static str = "settings";
+    /// Amount the volume changes with each press in the settings menu
+    pub const VOLUME_STEP: f32 = 0.1;
+
+    /// Change the volume by `steps` of [`Self::VOLUME_STEP`], staying within 0 and 1
+    pub fn step_volume(&mut self, steps: f32) {
+        let volume = self.volume + steps * Self::VOLUME_STEP;
+        // Round to whole steps to not accumulate floating point errors
+        let steps = (volume / Self::VOLUME_STEP).round();
+        self.volume = (steps * Self::VOLUME_STEP).clamp(0.0, 1.0);
+    }
+}
+
+/// Where the [`Settings`] are persisted
+pub trait SettingsStore: Send + Sync + 'static {
+    /// Stored settings, `None` when nothing usable is stored
+    fn load(&self) -> Option<Settings>;
+    /// Store the settings, failures are logged and otherwise ignored
+    fn save(&self, settings: &Settings);
+}
+
+/// Settings stored as a JSON file in a directory, by default the user data directory
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct FileSettingsStore {
+    dir: Option<std::path::PathBuf>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileSettingsStore {
+    /// Store the settings in `dir` instead of the user data directory
+    pub fn new(dir: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            dir: Some(dir.into()),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Default for FileSettingsStore {
+    fn default() -> Self {
+        Self {
+            dir: storage::data_dir(),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SettingsStore for FileSettingsStore {
+    fn load(&self) -> Option<Settings> {
+        storage::load_from(self.dir.as_deref()?, Settings::KEY)
+    }
+
+    fn save(&self, settings: &Settings) {
+        match &self.dir {
+            Some(dir) => storage::save_to(dir, Settings::KEY, settings),
+            None => warn!("No data directory available to store {}", Settings::KEY),
+        }
+    }
+}
+
+/// Settings stored in the `localStorage` of the browser
+#[cfg(target_arch = "wasm32")]
+#[derive(Debug, Default, Clone)]
+pub struct LocalSettingsStore;
+
+#[cfg(target_arch = "wasm32")]
+impl SettingsStore for LocalSettingsStore {
+    fn load(&self) -> Option<Settings> {
+        storage::load(Settings::KEY)
+    }
+
+    fn save(&self, settings: &Settings) {
+        storage::save(Settings::KEY, settings);
+    }
+}
+
+/// Store of the current platform
+#[cfg(not(target_arch = "wasm32"))]
+pub type PlatformSettingsStore = FileSettingsStore;
+/// Store of the current platform
+#[cfg(target_arch = "wasm32")]
+pub type PlatformSettingsStore = LocalSettingsStore;
+
+/// The [`SettingsStore`] in use, [`PlatformSettingsStore`] unless inserted before the
+/// [`crate::game::LoadingPlugin`]
+#[derive(Resource)]
+pub struct SettingsBackend(pub Box<dyn SettingsStore>);
+
+impl Default for SettingsBackend {
+    fn default() -> Self {
+        Self(Box::new(PlatformSettingsStore::default()))
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            grid_lines: false,
+            reaction_histogram: false,
+            spawn_trail: false,
+            confirm_quit: false,
+            text_outline: false,
+            spawn_sound: false,
+            min_clickable_scale: 0.0,
+            freeze_frame: 0.0,
+            background_pattern: BackgroundPattern::default(),
+            tile_values: false,
+            decay_overlay: true,
+            value_gradient: false,
+            metronome: false,
+            volume: 1.0,
+            muted: false,
+            haptics: true,
+            color_scheme: ColorScheme::default(),
+            font: FontChoice::default(),
+            spawn_pattern: SpawnPattern::default(),
+            key_bindings: KeyBindings::default(),
+            difficulty: Difficulty::default(),
+        }
+    }
+}
+
+/// Logical action of the keyboard controls
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Pause or resume the session
+    Pause,
+    /// Click the tile under the keyboard cursor, or start a session from the main menu
+    Confirm,
+    /// Start a new session from the game over menu
+    Restart,
+    /// Toggle [`Settings::muted`] during a session
+    Mute,
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+}
+
+/// Keys bound to each [`Action`], any of the keys triggers the action
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Resource)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub pause: Vec<KeyCode>,
+    pub confirm: Vec<KeyCode>,
+    pub restart: Vec<KeyCode>,
+    pub mute: Vec<KeyCode>,
+    pub move_up: Vec<KeyCode>,
+    pub move_down: Vec<KeyCode>,
+    pub move_left: Vec<KeyCode>,
+    pub move_right: Vec<KeyCode>,
+}
+
+impl KeyBindings {
+    /// Keys bound to `action`
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        match action {
+            Action::Pause => &self.pause,
+            Action::Confirm => &self.confirm,
+            Action::Restart => &self.restart,
+            Action::Mute => &self.mute,
+            Action::MoveUp => &self.move_up,
+            Action::MoveDown => &self.move_down,
+            Action::MoveLeft => &self.move_left,
+            Action::MoveRight => &self.move_right,
+        }
+    }
+
+    /// Whether a key bound to `action` was pressed this frame
+    pub fn just_pressed(&self, keys: &ButtonInput<KeyCode>, action: Action) -> bool {
+        keys.any_just_pressed(self.keys(action).iter().copied())
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            pause: vec![KeyCode::Escape],
+            confirm: vec![KeyCode::Enter, KeyCode::NumpadEnter, KeyCode::Space],
+            restart: vec![KeyCode::KeyR],
+            mute: vec![KeyCode::KeyM],
+            move_up: vec![KeyCode::ArrowUp],
+            move_down: vec![KeyCode::ArrowDown],
+            move_left: vec![KeyCode::ArrowLeft],
+            move_right: vec![KeyCode::ArrowRight],
+        }
+    }
+}
+
+/// Coloring of the empty cells of the field
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackgroundPattern {
+    /// Every cell has [`Palette::background`]
+    #[default]
+    Solid,
+    /// Alternate between [`Palette::background`] and [`Palette::background_alt`],
+    /// which makes it easier to judge cell positions
+    Checkerboard,
+    /// Blend from [`Palette::background`] at the top to [`Palette::background_alt`] at the bottom
+    Gradient,
+}
+
+impl BackgroundPattern {
+    /// All patterns in menu order
+    pub const ALL: &'static [Self] = &[Self::Solid, Self::Checkerboard, Self::Gradient];
+
+    /// The next pattern, wrapping around to the first
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|p| *p == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Color of the cell at `x`, `y` on a field with `rows` rows
+    pub fn color(self, palette: &Palette, x: u32, y: u32, rows: u32) -> Color {
+        match self {
+            Self::Solid => palette.background,
+            Self::Checkerboard if (x + y).is_multiple_of(2) => palette.background,
+            Self::Checkerboard => palette.background_alt,
+            Self::Gradient => {
+                let t = y as f32 / rows.saturating_sub(1).max(1) as f32;
+                mix(palette.background, palette.background_alt, t)
+            }
+        }
+    }
+}
+
+/// Placement of new tiles on the field
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpawnPattern {
+    /// Close to the previously spawned tile, widening the range when no cell nearby is free
+    #[default]
+    Clustered,
+    /// Any free cell with equal chance, which requires more movement
+    Uniform,
+}
+
+impl SpawnPattern {
+    /// All patterns in menu order
+    pub const ALL: &'static [Self] = &[Self::Clustered, Self::Uniform];
+
+    /// The next pattern, wrapping around to the first
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|p| *p == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+/// Blend linearly from `from` at `t` 0 to `to` at `t` 1
+fn mix(from: Color, to: Color, t: f32) -> Color {
+    let [r0, g0, b0, a0] = from.as_rgba_f32();
+    let [r1, g1, b1, a1] = to.as_rgba_f32();
+    let lerp = |a: f32, b: f32| a + (b - a) * t;
+    Color::rgba(lerp(r0, r1), lerp(g0, g1), lerp(b0, b1), lerp(a0, a1))
+}
+
+/// Bundled fonts which can be selected
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FontChoice {
+    /// EB Garamond
+    #[default]
+    Serif,
+    /// EB Garamond in bold
+    Bold,
+    /// Fira Mono built into Bevy, also used when loading another font fails
+    Mono,
+}
+
+impl FontChoice {
+    /// All fonts in menu order
+    pub const ALL: &'static [Self] = &[Self::Serif, Self::Bold, Self::Mono];
+
+    /// The next font, wrapping around to the first
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|f| *f == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Asset path of the font, `None` for the built-in font
+    pub fn path(self) -> Option<&'static str> {
+        match self {
+            Self::Serif => Some("fonts/EBGaramond-Regular.ttf"),
+            Self::Bold => Some("fonts/EBGaramond-Bold.ttf"),
+            Self::Mono => None,
+        }
+    }
+}
+
+/// Set of field and tile colors, some of which avoid colors that are hard to tell apart with color
+/// vision deficiencies
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorScheme {
+    #[default]
+    Default,
+    /// Avoids telling tiles apart by red and green only
+    Deuteranopia,
+    /// Like [`Self::Deuteranopia`], but without relying on red, which appears dark
+    Protanopia,
+    /// Pure black and white with saturated tiles
+    HighContrast,
+}
+
+impl ColorScheme {
+    /// All schemes in menu order
+    pub const ALL: &'static [Self] = &[
+        Self::Default,
+        Self::Deuteranopia,
+        Self::Protanopia,
+        Self::HighContrast,
+    ];
+
+    /// The next scheme, wrapping around to the first
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|s| *s == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+/// Colors used for drawing the game
+#[derive(Debug, Clone, Resource)]
+pub struct Palette {
+    /// Empty cell of the field
+    pub background: Color,
+    /// Second color of patterned backgrounds, see [`BackgroundPattern`]
+    pub background_alt: Color,
+    /// Tile which should be clicked
+    pub tile: Color,
+    /// Tile which was just spawned with [`Settings::value_gradient`]
+    pub tile_fresh: Color,
+    /// Tile without points left with [`Settings::value_gradient`]
+    pub tile_stale: Color,
+    /// Tile indicating a misclick
+    pub error: Color,
+    /// Flash of a clicked tile before it disappears
+    pub hit: Color,
+    /// Tile shuffling the other tiles
+    pub shuffle: Color,
+    /// Golden tile worth bonus points
+    pub bonus: Color,
+    /// Tile extending the session
+    pub time_bonus: Color,
+    /// Lines between cells
+    pub grid_line: Color,
+    /// Base color of the user interface, all button colors are derived from it
+    pub accent: Color,
+}
+
+impl Palette {
+    /// Muted red accent
+    pub const CRIMSON: Color = Color::rgb(0.5, 0.2, 0.2);
+    /// Muted green accent
+    pub const MOSS: Color = Color::rgb(0.25, 0.5, 0.2);
+    /// Muted blue accent
+    pub const OCEAN: Color = Color::rgb(0.2, 0.3, 0.55);
+    /// Accent presets which can be cycled through with [`Self::next_accent`]
+    pub const ACCENTS: &'static [Color] = &[Self::CRIMSON, Self::MOSS, Self::OCEAN];
+
+    /// Switch the field and tile colors to `scheme`, keeping the accent
+    pub fn set_scheme(&mut self, scheme: ColorScheme) {
+        *self = Self {
+            accent: self.accent,
+            ..Self::for_scheme(scheme)
+        };
+    }
+
+    /// Palette of `scheme` with the default accent
+    fn for_scheme(scheme: ColorScheme) -> Self {
+        let default = Self {
+            background: Color::rgb(0.8, 0.8, 0.8),
+            background_alt: Color::rgb(0.7, 0.7, 0.7),
+            tile: Color::rgb(0.1, 0.1, 0.1),
+            tile_fresh: Color::rgb(0.05, 0.15, 0.55),
+            tile_stale: Color::rgb(0.5, 0.5, 0.5),
+            error: Color::rgb(0.9, 0.1, 0.1),
+            hit: Color::WHITE,
+            shuffle: Color::rgb(0.45, 0.2, 0.6),
+            bonus: Color::rgb(0.85, 0.65, 0.13),
+            time_bonus: Color::rgb(0.2, 0.45, 0.9),
+            grid_line: Color::rgb(0.55, 0.55, 0.55),
+            accent: Self::CRIMSON,
+        };
+        // Based on the Okabe-Ito palette, which stays distinguishable with color blindness
+        match scheme {
+            ColorScheme::Default => default,
+            ColorScheme::Deuteranopia => Self {
+                error: Color::rgb(0.9, 0.6, 0.0),
+                shuffle: Color::rgb(0.8, 0.47, 0.65),
+                bonus: Color::rgb(0.95, 0.9, 0.25),
+                time_bonus: Color::rgb(0.35, 0.7, 0.9),
+                ..default
+            },
+            ColorScheme::Protanopia => Self {
+                error: Color::rgb(0.0, 0.45, 0.7),
+                shuffle: Color::rgb(0.8, 0.47, 0.65),
+                bonus: Color::rgb(0.95, 0.9, 0.25),
+                time_bonus: Color::rgb(0.0, 0.62, 0.45),
+                ..default
+            },
+            ColorScheme::HighContrast => Self {
+                background: Color::WHITE,
+                background_alt: Color::rgb(0.85, 0.85, 0.85),
+                tile: Color::BLACK,
+                tile_fresh: Color::rgb(0.0, 0.0, 0.6),
+                tile_stale: Color::rgb(0.45, 0.45, 0.45),
+                error: Color::rgb(1.0, 0.0, 0.0),
+                // The background is already white
+                hit: Color::rgb(1.0, 0.9, 0.0),
+                shuffle: Color::rgb(0.5, 0.0, 0.8),
+                bonus: Color::rgb(1.0, 0.75, 0.0),
+                time_bonus: Color::rgb(0.0, 0.3, 1.0),
+                grid_line: Color::BLACK,
+                ..default
+            },
+        }
+    }
+
+    /// Color of a tile with `fraction` of its points left, see [`Settings::value_gradient`]
+    pub fn tile_gradient(&self, fraction: f32) -> Color {
+        mix(self.tile_stale, self.tile_fresh, fraction.clamp(0.0, 1.0))
+    }
+
+    /// Switch to the next accent preset
+    pub fn next_accent(&mut self) {
+        let current = Self::ACCENTS.iter().position(|c| *c == self.accent);
+        let next = current.map_or(0, |i| (i + 1) % Self::ACCENTS.len());
+        self.accent = Self::ACCENTS[next];
+    }
+
+    /// Accent color with a given lightness
+    fn shade(&self, lightness: f32) -> Color {
+        self.accent.as_hsla().with_l(lightness)
+    }
+
+    /// Button without interaction
+    pub fn button_normal(&self) -> Color {
+        self.shade(0.15)
+    }
+
+    /// Button with cursor over it
+    pub fn button_hovered(&self) -> Color {
+        self.shade(0.25)
+    }
+
+    /// Button being pressed
+    pub fn button_pressed(&self) -> Color {
+        self.shade(0.5)
+    }
+
+    /// Border of buttons
+    pub fn button_border(&self) -> Color {
+        self.shade(0.4)
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::for_scheme(ColorScheme::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn file_store_round_trip() {
+        let dir =
+            std::env::temp_dir().join(format!("tile-clicker-settings-{}", std::process::id()));
+        let store = FileSettingsStore::new(&dir);
+        assert!(store.load().is_none());
+        let settings = Settings {
+            difficulty: Difficulty::Hard,
+            volume: 0.5,
+            ..default()
+        };
+        store.save(&settings);
+        let loaded = store.load().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(loaded.difficulty, Difficulty::Hard);
+        assert_eq!(loaded.volume, 0.5);
+    }
+}
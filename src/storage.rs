@@ -0,0 +1,183 @@
+//! # Storage
+//! Persist small values between runs of the game.
+//! Values are serialized as JSON and written to the user data directory,
+//! or to `localStorage` when running in the browser.
+//! Files meant for the player are exported with [`export`].
+//! The game loads and stores its values through the [`Storage`] resource of the app.
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+
+/// Where an app keeps its stored values, [`Storage::Platform`] unless inserted before the
+/// [`crate::GamePlugin`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+pub enum Storage {
+    /// The user data directory, or `localStorage` in the browser
+    #[default]
+    Platform,
+    /// Nothing is loaded or stored, for runs which must not depend on or change the stored values
+    /// like benchmarks and tests
+    Disabled,
+}
+
+impl Storage {
+    /// Storage of `app`, inserting the default when there is none yet
+    pub fn of(app: &mut App) -> Self {
+        *app.world.get_resource_or_insert_with(Self::default)
+    }
+
+    /// Load a value stored under `key`, see [`load`]
+    pub fn load<T: DeserializeOwned>(self, key: &str) -> Option<T> {
+        match self {
+            Self::Platform => load(key),
+            Self::Disabled => None,
+        }
+    }
+
+    /// Store a value under `key`, see [`save`]
+    pub fn save<T: Serialize>(self, key: &str, value: &T) {
+        match self {
+            Self::Platform => save(key, value),
+            Self::Disabled => (),
+        }
+    }
+}
+
+/// Load a value stored under `key`.
+/// Returns `None` when nothing is stored or the stored value can not be read.
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    decode(key, &read(key)?)
+}
+
+/// Store a value under `key`, failures are logged and otherwise ignored
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    if let Some(raw) = encode(key, value) {
+        write(key, &raw);
+    }
+}
+
+/// Like [`load`], but from `dir` instead of the user data directory
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_from<T: DeserializeOwned>(dir: &Path, key: &str) -> Option<T> {
+    decode(key, &std::fs::read_to_string(path_in(dir, key)).ok()?)
+}
+
+/// Like [`save`], but to `dir` instead of the user data directory
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_to<T: Serialize>(dir: &Path, key: &str, value: &T) {
+    if let Some(raw) = encode(key, value) {
+        write_file(&path_in(dir, key), &raw);
+    }
+}
+
+/// Directory of the stored values, `None` when the platform has no user data directory
+#[cfg(not(target_arch = "wasm32"))]
+pub fn data_dir() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join("tile-clicker"))
+}
+
+fn decode<T: DeserializeOwned>(key: &str, raw: &str) -> Option<T> {
+    match serde_json::from_str(raw) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            warn!("Ignoring corrupt stored value {}: {}", key, e);
+            None
+        }
+    }
+}
+
+fn encode<T: Serialize>(key: &str, value: &T) -> Option<String> {
+    serde_json::to_string(value)
+        .map_err(|e| warn!("Failed serializing {}: {}", key, e))
+        .ok()
+}
+
+/// Hand a file to the player.
+/// It is written to `name` relative to the working directory, or downloaded in the browser.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn export(name: &str, contents: &str) {
+    match std::fs::write(name, contents) {
+        Ok(()) => info!("Exported {}", name),
+        Err(e) => warn!("Failed exporting {}: {}", name, e),
+    }
+}
+
+/// Hand a file to the player.
+/// It is written to `name` relative to the working directory, or downloaded in the browser.
+#[cfg(target_arch = "wasm32")]
+pub fn export(name: &str, contents: &str) {
+    if download(name, contents).is_none() {
+        warn!("Failed downloading {}", name);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn download(name: &str, contents: &str) -> Option<()> {
+    use wasm_bindgen::JsCast;
+    let parts = js_sys::Array::of1(&wasm_bindgen::JsValue::from_str(contents));
+    let blob = web_sys::Blob::new_with_str_sequence(&parts).ok()?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).ok()?;
+    let anchor: web_sys::HtmlAnchorElement = web_sys::window()?
+        .document()?
+        .create_element("a")
+        .ok()?
+        .dyn_into()
+        .ok()?;
+    anchor.set_href(&url);
+    anchor.set_download(name);
+    anchor.click();
+    web_sys::Url::revoke_object_url(&url).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn path_in(dir: &Path, key: &str) -> PathBuf {
+    dir.join(key).with_extension("json")
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(key: &str) -> Option<String> {
+    std::fs::read_to_string(path_in(&data_dir()?, key)).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(key: &str, raw: &str) {
+    let Some(dir) = data_dir() else {
+        warn!("No data directory available to store {}", key);
+        return;
+    };
+    write_file(&path_in(&dir, key), raw);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_file(path: &Path, raw: &str) {
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(path, raw));
+    if let Err(e) = result {
+        warn!("Failed writing {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(key: &str) -> Option<String> {
+    local_storage()?.get_item(key).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(key: &str, raw: &str) {
+    let Some(storage) = local_storage() else {
+        warn!("No local storage available to store {}", key);
+        return;
+    };
+    if storage.set_item(key, raw).is_err() {
+        warn!("Failed writing {} to local storage", key);
+    }
+}
@@ -1,12 +1,14 @@
 use bevy::prelude::*;
 
 use crate::despawn_on_screen;
-use crate::game::{Assets, GameState, OnGameScreen, OnSessionScreen, RunningState};
-
-pub const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
-pub const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
-pub const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
+use crate::game::{
+    Assets, Countdown, Difficulty, GameMode, GameOverReason, GameRng, GameState, GamepadInput,
+    GridConfig, HighScore, Leaderboard, LifetimeStats, LoadErrors, OnGameScreen, OnSessionScreen,
+    ReactionTimes, RunningState, Score, SessionStats, GAME_DURATION,
+};
+use crate::settings::{Action, KeyBindings, Palette, Settings, SettingsBackend};
 
+/// Plugin showing the menus and the overlays of a session
 pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
@@ -14,13 +16,84 @@ impl Plugin for UiPlugin {
         app.add_systems(OnEnter(GameState::Menu), setup_main_menu)
             .add_systems(
                 Update,
-                main_menu_button_system.run_if(in_state(GameState::Menu)),
+                (
+                    (main_menu_button_system, start_with_keyboard)
+                        .run_if(not(resource_exists::<Transition>)),
+                    update_main_menu_labels,
+                )
+                    .run_if(in_state(GameState::Menu)),
             )
+            .add_systems(Update, run_transition.run_if(resource_exists::<Transition>))
             .add_systems(OnExit(GameState::Menu), despawn_on_screen::<OnMainMenu>)
-            .add_systems(OnEnter(RunningState::Finished), setup_game_menu)
+            .add_systems(Update, (apply_palette, tween_button_colors).chain())
+            .add_systems(Update, gamepad_menu_navigation)
+            .add_systems(OnEnter(GameState::Stats), setup_stats_screen)
+            .add_systems(
+                Update,
+                back_button_system.run_if(
+                    in_state(GameState::Stats)
+                        .or_else(in_state(GameState::Settings))
+                        .or_else(in_state(GameState::Leaderboard))
+                        .or_else(in_state(GameState::Help)),
+                ),
+            )
+            .add_systems(OnExit(GameState::Stats), despawn_on_screen::<OnStatsScreen>)
+            .add_systems(OnEnter(GameState::LoadError), setup_load_error_screen)
             .add_systems(
                 Update,
-                game_menu_button_system.run_if(in_state(RunningState::Finished)),
+                retry_button_system.run_if(in_state(GameState::LoadError)),
+            )
+            .add_systems(
+                OnExit(GameState::LoadError),
+                despawn_on_screen::<OnLoadErrorScreen>,
+            )
+            .add_systems(OnEnter(GameState::Leaderboard), setup_leaderboard_screen)
+            .add_systems(
+                OnExit(GameState::Leaderboard),
+                despawn_on_screen::<OnLeaderboardScreen>,
+            )
+            .add_systems(OnEnter(GameState::Help), setup_help_screen)
+            .add_systems(OnExit(GameState::Help), despawn_on_screen::<OnHelpScreen>)
+            .add_systems(OnEnter(GameState::Settings), setup_settings_screen)
+            .add_systems(
+                Update,
+                (settings_button_system, update_settings_labels)
+                    .chain()
+                    .run_if(in_state(GameState::Settings)),
+            )
+            .add_systems(
+                OnExit(GameState::Settings),
+                (despawn_on_screen::<OnSettingsScreen>, save_settings),
+            )
+            .add_systems(OnEnter(GameState::Game), setup_mute_button)
+            .add_systems(
+                Update,
+                (mute_button_system, mute_with_keyboard).run_if(in_state(GameState::Game)),
+            )
+            .add_systems(OnEnter(RunningState::Countdown), setup_countdown_overlay)
+            .add_systems(
+                Update,
+                update_countdown_text.run_if(in_state(RunningState::Countdown)),
+            )
+            .add_systems(
+                OnExit(RunningState::Countdown),
+                despawn_on_screen::<OnCountdownScreen>,
+            )
+            .add_systems(OnEnter(RunningState::Paused), setup_pause_overlay)
+            .add_systems(
+                OnExit(RunningState::Paused),
+                despawn_on_screen::<OnPauseScreen>,
+            )
+            .add_systems(OnEnter(RunningState::Finished), start_freeze_frame)
+            .add_systems(
+                Update,
+                (
+                    tick_freeze_frame,
+                    setup_game_menu.run_if(freeze_frame_just_finished),
+                    game_menu_button_system.run_if(resource_exists::<MenuActiveDelay>),
+                )
+                    .chain()
+                    .run_if(in_state(RunningState::Finished)),
             );
     }
 }
@@ -29,13 +102,117 @@ impl Plugin for UiPlugin {
 #[derive(Debug, Component)]
 pub struct OnMainMenu;
 
+/// Tag for entities of the lifetime statistics screen
+#[derive(Debug, Component)]
+struct OnStatsScreen;
+
+/// Tag for entities of the screen listing assets which failed to load
+#[derive(Debug, Component)]
+struct OnLoadErrorScreen;
+
+/// Tag for entities of the leaderboard screen
+#[derive(Debug, Component)]
+struct OnLeaderboardScreen;
+
+/// Tag for entities of the settings screen
+#[derive(Debug, Component)]
+struct OnSettingsScreen;
+
+/// Tag for entities of the screen explaining the rules
+#[derive(Debug, Component)]
+struct OnHelpScreen;
+
+/// Tag for the text showing the [`Settings::volume`]
+#[derive(Debug, Clone, Component)]
+struct VolumeText;
+
+/// Buttons in the settings menu
+#[derive(Debug, Clone, Copy, Component)]
+enum SettingsButton {
+    /// Decrease the volume by [`Settings::VOLUME_STEP`]
+    Quieter,
+    /// Increase the volume by [`Settings::VOLUME_STEP`]
+    Louder,
+    /// Cycle through the accent color presets
+    Accent,
+    /// Cycle through the [`crate::settings::BackgroundPattern`]s
+    Pattern,
+    /// Cycle through the [`crate::settings::ColorScheme`]s
+    Scheme,
+    /// Cycle through the [`crate::settings::FontChoice`]s
+    Font,
+    /// Cycle through the [`crate::settings::SpawnPattern`]s
+    Spawns,
+}
+
+impl SettingsButton {
+    /// All buttons to be displayed in the settings menu
+    const ALL: &'static [Self] = &[
+        Self::Quieter,
+        Self::Louder,
+        Self::Accent,
+        Self::Pattern,
+        Self::Scheme,
+        Self::Font,
+        Self::Spawns,
+    ];
+
+    /// Text displayed on the button
+    fn label(self, settings: &Settings) -> String {
+        match self {
+            Self::Quieter => "Volume -".into(),
+            Self::Louder => "Volume +".into(),
+            Self::Accent => "Accent".into(),
+            Self::Pattern => format!("{:?}", settings.background_pattern),
+            Self::Scheme => format!("{:?}", settings.color_scheme),
+            Self::Font => format!("{:?}", settings.font),
+            Self::Spawns => format!("{:?} spawns", settings.spawn_pattern),
+        }
+    }
+}
+
+/// Button in the score bar toggling [`Settings::muted`]
+#[derive(Debug, Component)]
+struct MuteButton;
+
+impl MuteButton {
+    /// Text displayed on the button
+    fn label(muted: bool) -> &'static str {
+        if muted {
+            "Muted"
+        } else {
+            "Sound"
+        }
+    }
+}
+
+/// Tag for entities of the countdown overlay
+#[derive(Debug, Component)]
+struct OnCountdownScreen;
+
+/// Tag for the text showing the seconds left of the [`Countdown`]
+#[derive(Debug, Clone, Component)]
+struct CountdownText;
+
+/// Tag for entities of the pause overlay
+#[derive(Debug, Component)]
+struct OnPauseScreen;
+
+/// Button returning to the main menu
+#[derive(Debug, Clone, Copy, Component)]
+struct BackButton;
+
+/// Button loading the assets again after a failure
+#[derive(Debug, Clone, Copy, Component)]
+struct RetryButton;
+
 /// Style used in buttons
 macro_rules! BUTTON_STYLE {
     () => {
         Style {
             width: Val::Percent(50.0),
-            height: Val::Percent(15.0),
-            margin: UiRect::new(Val::Auto, Val::Auto, Val::Px(10.0), Val::Px(10.0)),
+            height: Val::Percent(7.0),
+            margin: UiRect::new(Val::Auto, Val::Auto, Val::Px(5.0), Val::Px(5.0)),
             justify_content: JustifyContent::Center,
             align_items: AlignItems::Center,
             border: UiRect::all(Val::Px(2.0)),
@@ -45,20 +222,162 @@ macro_rules! BUTTON_STYLE {
 }
 
 /// Buttons in the menu
-#[derive(Debug, Clone, Copy, Component)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
 enum MainMenuButton {
     Start,
+    /// Start a game which is won by reaching the target score, see [`GameMode::Target`]
+    Target,
+    /// Start a game lasting until a misclick
+    Endless,
+    /// Start a game highlighting which tile to click, see [`GameMode::Practice`]
+    Practice,
+    /// Start a calm game without score or time, see [`GameMode::Zen`]
+    Zen,
+    /// Cycle through the difficulties
+    Difficulty,
+    /// Cycle through the [`GridConfig::PRESETS`]
+    Board,
+    /// Show the lifetime statistics
+    Stats,
+    /// Show the best sessions
+    Leaderboard,
+    /// Explain the rules
+    Help,
+    /// Show the settings menu
+    Settings,
+    /// Close the game, not available in the browser
+    #[cfg(not(target_arch = "wasm32"))]
+    Quit,
 }
 
 impl MainMenuButton {
     /// All buttons to be displayed in the menu
-    const ALL: &'static [Self] = &[Self::Start];
+    const ALL: &'static [Self] = &[
+        Self::Start,
+        Self::Target,
+        Self::Endless,
+        Self::Practice,
+        Self::Zen,
+        Self::Difficulty,
+        Self::Board,
+        Self::Stats,
+        Self::Leaderboard,
+        Self::Help,
+        Self::Settings,
+        #[cfg(not(target_arch = "wasm32"))]
+        Self::Quit,
+    ];
+
+    /// Text displayed on the button
+    fn label(self, difficulty: Difficulty, board: GridConfig) -> String {
+        match self {
+            Self::Difficulty => format!("{:?}", difficulty),
+            Self::Board => format!("{}x{}", board.cols, board.rows),
+            Self::Help => "How to play".into(),
+            _ => format!("{:?}", self),
+        }
+    }
+}
+
+/// Marker of buttons created with [`menu_button`], which can be focused with a gamepad
+#[derive(Debug, Component)]
+struct MenuFocusable;
+
+/// Background color a button fades to, the interaction handlers set the `target`
+/// and [`tween_button_colors`] blends towards it in [`ButtonColorTween::DURATION`]
+#[derive(Debug, Component)]
+struct ButtonColorTween {
+    target: Color,
+    timer: Timer,
+}
+
+impl ButtonColorTween {
+    /// Seconds a color change takes
+    const DURATION: f32 = 0.1;
+
+    fn new(color: Color) -> Self {
+        Self {
+            target: color,
+            timer: Timer::from_seconds(Self::DURATION, TimerMode::Once),
+        }
+    }
+
+    /// Fade to `color`, starting from the color currently shown
+    fn retarget(&mut self, color: Color) {
+        self.target = color;
+        self.timer.reset();
+    }
+}
+
+/// Blend the button colors linearly towards their [`ButtonColorTween::target`].
+/// Uses real time, the game menu is shown while the game clock is paused.
+fn tween_button_colors(
+    time: Res<Time<Real>>,
+    mut buttons: Query<(&mut ButtonColorTween, &mut BackgroundColor)>,
+) {
+    for (mut tween, mut color) in &mut buttons {
+        if tween.timer.finished() {
+            continue;
+        }
+        let remaining = tween.timer.remaining_secs();
+        tween.timer.tick(time.delta());
+        let step = (time.delta_seconds() / remaining).min(1.0);
+        let current = Vec4::from(color.0.as_rgba_f32());
+        let target = Vec4::from(tween.target.as_rgba_f32());
+        color.0 = Color::rgba_from_array(current.lerp(target, step));
+    }
 }
 
 /// Timer to disable first 0.8s of menu interaction
 #[derive(Debug, Resource)]
 struct MenuActiveDelay(Timer);
 
+/// Real time the final board stays visible before the menu appears
+#[derive(Debug, Resource)]
+struct FreezeFrame(Timer);
+
+/// Fade through black from the main menu into a session.
+/// The state only changes once the screen is black, the [`TransitionOverlay`] is removed
+/// after fading back in.
+#[derive(Debug, Resource)]
+struct Transition {
+    timer: Timer,
+    direction: FadeDirection,
+}
+
+impl Transition {
+    /// Real seconds of each half of the transition
+    const SECS: f32 = 0.25;
+
+    fn new(direction: FadeDirection) -> Self {
+        Self {
+            timer: Timer::from_seconds(Self::SECS, TimerMode::Once),
+            direction,
+        }
+    }
+
+    /// Opacity of the [`TransitionOverlay`]
+    fn alpha(&self) -> f32 {
+        match self.direction {
+            FadeDirection::Out => self.timer.fraction(),
+            FadeDirection::In => self.timer.fraction_remaining(),
+        }
+    }
+}
+
+/// Half of a [`Transition`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FadeDirection {
+    /// Darken the main menu
+    Out,
+    /// Reveal the session
+    In,
+}
+
+/// Black node covering the screen during a [`Transition`], kept across the state change
+#[derive(Debug, Component)]
+struct TransitionOverlay;
+
 /// Kinds of buttons in menu
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
 enum GameMenuButton {
@@ -73,8 +392,171 @@ impl GameMenuButton {
     const ALL: &'static [Self] = &[Self::Menu, Self::Restart];
 }
 
+/// Spawn a text node.
+/// With `outline` a dark copy is drawn slightly offset behind the text to keep it readable on
+/// bright backgrounds. `marker` is inserted on the text and its copy.
+/// Returns the outermost entity, which has `style` applied.
+pub fn outlined_text<B: Bundle + Clone>(
+    commands: &mut Commands,
+    text: Text,
+    style: Style,
+    outline: bool,
+    marker: B,
+) -> Entity {
+    if !outline {
+        return commands
+            .spawn((
+                TextBundle {
+                    text,
+                    style,
+                    ..default()
+                },
+                marker,
+            ))
+            .id();
+    }
+    let mut shadow = text.clone();
+    for section in shadow.sections.iter_mut() {
+        section.style.color = Color::rgba(0.0, 0.0, 0.0, 0.8);
+    }
+    let node = commands.spawn(NodeBundle { style, ..default() }).id();
+    commands
+        .spawn((
+            TextBundle {
+                text: shadow,
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(2.0),
+                    top: Val::Px(2.0),
+                    ..default()
+                },
+                ..default()
+            },
+            marker.clone(),
+        ))
+        .set_parent(node);
+    commands
+        .spawn((TextBundle { text, ..default() }, marker))
+        .set_parent(node);
+    node
+}
+
+/// Spawn a menu button with a text label as child of `parent`
+fn menu_button<B: Component>(
+    commands: &mut Commands,
+    parent: Entity,
+    button: B,
+    label: impl Into<String>,
+    font: &Handle<Font>,
+    palette: &Palette,
+    settings: &Settings,
+) {
+    let button_frame = commands
+        .spawn(ButtonBundle {
+            style: BUTTON_STYLE!(),
+            background_color: palette.button_normal().into(),
+            border_color: palette.button_border().into(),
+            ..default()
+        })
+        .insert((
+            button,
+            MenuFocusable,
+            ButtonColorTween::new(palette.button_normal()),
+        ))
+        .set_parent(parent)
+        .id();
+    let text = Text::from_section(
+        label,
+        TextStyle {
+            font: font.clone(),
+            font_size: 32.0,
+            color: Color::rgb(0.9, 0.9, 0.9),
+        },
+    );
+    let label = outlined_text(commands, text, default(), settings.text_outline, ());
+    commands.entity(label).set_parent(button_frame);
+}
+
+/// Move the focus between the buttons on screen with a gamepad and press the focused one.
+/// The focused button gets a light border while navigating.
+#[allow(clippy::type_complexity)]
+fn gamepad_menu_navigation(
+    mut gamepad: GamepadInput,
+    mut buttons: Query<
+        (Entity, &GlobalTransform, &mut Interaction, &mut BorderColor),
+        With<MenuFocusable>,
+    >,
+    added: Query<(), Added<MenuFocusable>>,
+    palette: Res<Palette>,
+    mut focus: Local<usize>,
+    mut pressed: Local<Option<Entity>>,
+) {
+    // Release the button pressed in the previous frame, the mouse would release it as well
+    if let Some(Ok((_, _, mut interaction, _))) = pressed.take().map(|e| buttons.get_mut(e)) {
+        interaction.set_if_neq(Interaction::None);
+    }
+    // A new screen starts with its first button
+    if !added.is_empty() {
+        *focus = 0;
+    }
+    let direction = gamepad.direction();
+    let confirm = gamepad.confirm();
+    if direction.y == 0 && !confirm {
+        return;
+    }
+    // Buttons are laid out from top to bottom
+    let mut order: Vec<_> = buttons
+        .iter()
+        .map(|(entity, transform, _, _)| (entity, transform.translation().y))
+        .collect();
+    if order.is_empty() {
+        return;
+    }
+    order.sort_by(|a, b| a.1.total_cmp(&b.1));
+    let last = order.len() as i32 - 1;
+    *focus = (*focus as i32 + direction.y).clamp(0, last) as usize;
+    let focused = order[*focus].0;
+    for (entity, _, mut interaction, mut border) in &mut buttons {
+        if entity == focused {
+            *border = Color::WHITE.into();
+            if confirm {
+                *interaction = Interaction::Pressed;
+                *pressed = Some(entity);
+            }
+        } else {
+            *border = palette.button_border().into();
+        }
+    }
+}
+
+/// Replace the label of a button created with [`menu_button`]
+fn set_button_label(
+    button: &Children,
+    label: &str,
+    nodes: &Query<&Children>,
+    texts: &mut Query<&mut Text>,
+) {
+    // The label is either a direct child or nested in an outline node
+    let labels = button.iter().flat_map(|child| {
+        let nested = nodes.get(*child).into_iter().flat_map(|c| c.iter());
+        std::iter::once(child).chain(nested)
+    });
+    for entity in labels {
+        if let Ok(mut text) = texts.get_mut(*entity) {
+            text.sections[0].value = label.into();
+        }
+    }
+}
+
 /// Create the menu
-fn setup_main_menu(mut commands: Commands, assets: Res<Assets>) {
+fn setup_main_menu(
+    mut commands: Commands,
+    assets: Res<Assets>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    difficulty: Res<Difficulty>,
+    board: Res<GridConfig>,
+) {
     commands.spawn(Camera2dBundle::default()).insert(OnMainMenu);
 
     let menu_node = commands
@@ -93,31 +575,265 @@ fn setup_main_menu(mut commands: Commands, assets: Res<Assets>) {
         .insert(OnMainMenu)
         .id();
     for button in MainMenuButton::ALL.iter() {
-        let button_frame = commands
-            .spawn(ButtonBundle {
-                style: BUTTON_STYLE!(),
-                background_color: Color::rgb(0.2, 0.2, 0.2).into(),
-                border_color: Color::rgb(0.5, 0.2, 0.2).into(),
+        let label = button.label(*difficulty, *board);
+        menu_button(
+            &mut commands,
+            menu_node,
+            *button,
+            label,
+            &assets.font,
+            &palette,
+            &settings,
+        );
+    }
+}
+
+/// Show a hint how to resume on top of the paused board
+fn setup_pause_overlay(mut commands: Commands, assets: Res<Assets>, settings: Res<Settings>) {
+    let node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
                 ..default()
-            })
-            .insert(*button)
-            .set_parent(menu_node)
-            .id();
-        commands
-            .spawn(TextBundle::from_section(
-                format!("{:?}", button),
-                TextStyle {
-                    font: assets.font.clone(),
-                    font_size: 40.0,
-                    color: Color::rgb(0.9, 0.9, 0.9),
-                },
-            ))
-            .set_parent(button_frame);
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.4).into(),
+            ..default()
+        })
+        .insert(OnPauseScreen)
+        .id();
+    let text = Text::from_section(
+        "Paused - press Escape or click to resume",
+        TextStyle {
+            font: assets.font.clone(),
+            font_size: 40.0,
+            color: Color::WHITE,
+        },
+    );
+    let text = outlined_text(&mut commands, text, default(), settings.text_outline, ());
+    commands.entity(text).set_parent(node);
+}
+
+/// Small mute toggle in the top right corner of the score bar
+fn setup_mute_button(
+    mut commands: Commands,
+    assets: Res<Assets>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+) {
+    let button = commands
+        .spawn(ButtonBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(5.0),
+                top: Val::Px(5.0),
+                width: Val::Px(80.0),
+                height: Val::Px(36.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                border: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            background_color: palette.button_normal().into(),
+            border_color: palette.button_border().into(),
+            ..default()
+        })
+        .insert((
+            MuteButton,
+            OnGameScreen,
+            ButtonColorTween::new(palette.button_normal()),
+        ))
+        .id();
+    let text = Text::from_section(
+        MuteButton::label(settings.muted),
+        TextStyle {
+            font: assets.font.clone(),
+            font_size: 24.0,
+            color: Color::rgb(0.9, 0.9, 0.9),
+        },
+    );
+    let label = outlined_text(&mut commands, text, default(), settings.text_outline, ());
+    commands.entity(label).set_parent(button);
+}
+
+/// Toggle [`Settings::muted`] and store it, so it is kept for later sessions and runs
+#[allow(clippy::type_complexity)]
+fn mute_button_system(
+    mut interaction_query: Query<
+        (&Interaction, &mut ButtonColorTween, &Children),
+        (Changed<Interaction>, With<MuteButton>),
+    >,
+    palette: Res<Palette>,
+    mut settings: ResMut<Settings>,
+    backend: Res<SettingsBackend>,
+    nodes: Query<&Children>,
+    mut texts: Query<&mut Text>,
+) {
+    for (interaction, mut tween, children) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                tween.retarget(palette.button_pressed());
+                settings.muted = !settings.muted;
+                info!("Muted: {}", settings.muted);
+                set_button_label(
+                    children,
+                    MuteButton::label(settings.muted),
+                    &nodes,
+                    &mut texts,
+                );
+                backend.0.save(&settings);
+            }
+            Interaction::Hovered => {
+                tween.retarget(palette.button_hovered());
+            }
+            Interaction::None => {
+                tween.retarget(palette.button_normal());
+            }
+        }
     }
 }
 
+/// Toggle [`Settings::muted`] with [`Action::Mute`], like the [`MuteButton`].
+/// On the game over menu M selects [`GameMenuButton::Menu`] instead.
+#[allow(clippy::too_many_arguments)]
+fn mute_with_keyboard(
+    keys: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    running_state: Res<State<RunningState>>,
+    mut settings: ResMut<Settings>,
+    backend: Res<SettingsBackend>,
+    buttons: Query<&Children, With<MuteButton>>,
+    nodes: Query<&Children>,
+    mut texts: Query<&mut Text>,
+) {
+    if *running_state.get() == RunningState::Finished || !bindings.just_pressed(&keys, Action::Mute)
+    {
+        return;
+    }
+    settings.muted = !settings.muted;
+    info!("Muted: {}", settings.muted);
+    for children in &buttons {
+        set_button_label(
+            children,
+            MuteButton::label(settings.muted),
+            &nodes,
+            &mut texts,
+        );
+    }
+    backend.0.save(&settings);
+}
+
+/// Show the seconds left of the [`Countdown`] in the center of the screen,
+/// with a hint how to stop a [`GameMode::Practice`] or [`GameMode::Zen`] session
+fn setup_countdown_overlay(
+    mut commands: Commands,
+    assets: Res<Assets>,
+    settings: Res<Settings>,
+    countdown: Res<Countdown>,
+    mode: Res<GameMode>,
+) {
+    let node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(OnCountdownScreen)
+        .id();
+    let text = Text::from_section(
+        countdown_label(&countdown),
+        TextStyle {
+            font: assets.font.clone(),
+            font_size: 120.0,
+            color: Color::WHITE,
+        },
+    );
+    let text = outlined_text(
+        &mut commands,
+        text,
+        default(),
+        settings.text_outline,
+        CountdownText,
+    );
+    commands.entity(text).set_parent(node);
+    let hint = match *mode {
+        GameMode::Zen => Some("Press Q to stop"),
+        GameMode::Practice => Some("Press Q to stop practicing"),
+        _ => None,
+    };
+    if let Some(hint) = hint {
+        let hint = Text::from_section(
+            hint,
+            TextStyle {
+                font: assets.font.clone(),
+                font_size: 32.0,
+                color: Color::WHITE,
+            },
+        );
+        let hint = outlined_text(&mut commands, hint, default(), settings.text_outline, ());
+        commands.entity(hint).set_parent(node);
+    }
+}
+
+/// Whole seconds left of the countdown, starting at the full duration
+fn countdown_label(countdown: &Countdown) -> String {
+    countdown.0.remaining_secs().ceil().max(1.0).to_string()
+}
+
+/// Keep the countdown number up to date
+fn update_countdown_text(
+    countdown: Res<Countdown>,
+    mut texts: Query<&mut Text, With<CountdownText>>,
+) {
+    for mut text in &mut texts {
+        text.sections[0].value = countdown_label(&countdown);
+    }
+}
+
+/// Freeze the final board for [`Settings::freeze_frame`] seconds.
+/// The game already paused the virtual time when the session finished.
+fn start_freeze_frame(mut commands: Commands, settings: Res<Settings>) {
+    commands.remove_resource::<MenuActiveDelay>();
+    commands.insert_resource(FreezeFrame(Timer::from_seconds(
+        settings.freeze_frame,
+        TimerMode::Once,
+    )));
+}
+
+/// Update the [`FreezeFrame`] timer
+fn tick_freeze_frame(mut freeze: ResMut<FreezeFrame>, time: Res<Time<Real>>) {
+    freeze.0.tick(time.delta());
+}
+
+/// Run condition for showing the menu after the [`FreezeFrame`]
+fn freeze_frame_just_finished(freeze: Res<FreezeFrame>) -> bool {
+    freeze.0.just_finished()
+}
+
 /// Setup a menu
-fn setup_game_menu(mut commands: Commands, assets: Res<Assets>) {
+#[allow(clippy::too_many_arguments)]
+fn setup_game_menu(
+    mut commands: Commands,
+    assets: Res<Assets>,
+    settings: Res<Settings>,
+    palette: Res<Palette>,
+    reaction_times: Res<ReactionTimes>,
+    reason: Res<GameOverReason>,
+    mode: Res<GameMode>,
+    score: Res<Score>,
+    high_score: Res<HighScore>,
+    stats: Res<SessionStats>,
+    rng: Res<GameRng>,
+) {
     // Prevent accidental clicking on menu item just after the game has ended
     commands.insert_resource(MenuActiveDelay(Timer::from_seconds(0.8, TimerMode::Once)));
     let ui_node = commands
@@ -136,92 +852,857 @@ fn setup_game_menu(mut commands: Commands, assets: Res<Assets>) {
         .insert(OnSessionScreen)
         .insert(OnGameScreen)
         .id();
+    let header = match (*reason, *mode) {
+        (GameOverReason::TargetReached, _) => "You win!",
+        (GameOverReason::TimeUp, GameMode::Target) => "Time's up",
+        (GameOverReason::TimeUp, _) => "Finished",
+        (GameOverReason::Misclick, _) => "Missed!",
+        (GameOverReason::TileExpired, _) => "Too slow!",
+        (GameOverReason::Completed, _) => "Completed",
+        (GameOverReason::Stopped, GameMode::Zen) => "Session over",
+        (GameOverReason::Stopped, _) => "Practice over",
+    };
+    let text = Text::from_section(
+        header,
+        TextStyle {
+            font: assets.font.clone(),
+            font_size: 52.0,
+            color: Color::WHITE,
+        },
+    );
+    let style = Style {
+        align_self: AlignSelf::Center,
+        ..default()
+    };
+    let header = outlined_text(&mut commands, text, style, settings.text_outline, ());
+    commands.entity(header).set_parent(ui_node);
+    let style = TextStyle {
+        font: assets.font.clone(),
+        font_size: 36.0,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+    let text = Text::from_sections([
+        TextSection::new(format!("Score: {}", score.0), style.clone()),
+        TextSection::new(format!("  Best: {}", high_score.0), style),
+    ]);
+    let style = Style {
+        align_self: AlignSelf::Center,
+        ..default()
+    };
+    let scores = outlined_text(&mut commands, text, style, settings.text_outline, ());
+    commands.entity(scores).set_parent(ui_node);
+    let accuracy = stats
+        .accuracy()
+        .map_or("-".into(), |accuracy| format!("{:.0}%", accuracy));
+    let lines = [
+        format!("Accuracy: {}  Best combo: {}", accuracy, stats.max_combo),
+        format!("Tiles: {}  Expired: {}", stats.hits, stats.tiles_expired),
+        format!("Seed: {}", rng.seed),
+    ];
+    for line in lines {
+        let text = Text::from_section(
+            line,
+            TextStyle {
+                font: assets.font.clone(),
+                font_size: 28.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            },
+        );
+        let style = Style {
+            align_self: AlignSelf::Center,
+            ..default()
+        };
+        let line = outlined_text(&mut commands, text, style, settings.text_outline, ());
+        commands.entity(line).set_parent(ui_node);
+    }
+    if settings.reaction_histogram {
+        reaction_histogram(&mut commands, ui_node, &reaction_times, assets.font.clone());
+    }
     for button in GameMenuButton::ALL.iter() {
-        let button_frame = commands
-            .spawn(ButtonBundle {
-                style: BUTTON_STYLE!(),
-                background_color: Color::rgb(0.2, 0.2, 0.2).into(),
-                border_color: Color::rgb(0.5, 0.2, 0.2).into(),
+        let label = format!("{:?}", button);
+        menu_button(
+            &mut commands,
+            ui_node,
+            *button,
+            label,
+            &assets.font,
+            &palette,
+            &settings,
+        );
+    }
+}
+
+/// Show the totals over all played games
+fn setup_stats_screen(
+    mut commands: Commands,
+    assets: Res<Assets>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    stats: Res<LifetimeStats>,
+) {
+    commands.spawn((Camera2dBundle::default(), OnStatsScreen));
+    let node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(90.0),
+                padding: UiRect::new(Val::Auto, Val::Auto, Val::Px(10.0), Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(OnStatsScreen)
+        .id();
+    let play_time = stats.play_time.as_secs();
+    let lines = [
+        format!("Games played: {}", stats.games_played),
+        format!("Tiles cleared: {}", stats.tiles_cleared),
+        format!("Misses: {}", stats.misses),
+        format!(
+            "Play time: {}h {:02}m {:02}s",
+            play_time / 3600,
+            play_time / 60 % 60,
+            play_time % 60
+        ),
+        match stats.fastest_reaction {
+            Some(time) => format!("Fastest reaction: {}ms", time.as_millis()),
+            None => "Fastest reaction: -".to_string(),
+        },
+    ];
+    for line in lines {
+        let text = Text::from_section(
+            line,
+            TextStyle {
+                font: assets.font.clone(),
+                font_size: 36.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            },
+        );
+        let style = Style {
+            align_self: AlignSelf::Center,
+            ..default()
+        };
+        let line = outlined_text(&mut commands, text, style, settings.text_outline, ());
+        commands.entity(line).set_parent(node);
+    }
+    menu_button(
+        &mut commands,
+        node,
+        BackButton,
+        "Back",
+        &assets.font,
+        &palette,
+        &settings,
+    );
+}
+
+/// Explain the rules of a session with the selected [`Difficulty`]
+fn setup_help_screen(
+    mut commands: Commands,
+    assets: Res<Assets>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    difficulty: Res<Difficulty>,
+) {
+    commands.spawn((Camera2dBundle::default(), OnHelpScreen));
+    let node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(90.0),
+                padding: UiRect::new(Val::Px(20.0), Val::Px(20.0), Val::Px(10.0), Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(OnHelpScreen)
+        .id();
+    let misses = match difficulty.lives() {
+        1 => "A click on an empty cell ends the game.".to_string(),
+        lives => format!("A click on an empty cell costs a life, you have {}.", lives),
+    };
+    let lines = [
+        "Click the tiles as soon as they appear.".to_string(),
+        format!(
+            "A tile is worth the most points right away and nothing after {}s.",
+            difficulty.tile_timing().decay_secs
+        ),
+        "Tiles spawn faster and lose their points quicker as the game goes on.".to_string(),
+        misses,
+        format!("The game ends after {}s.", GAME_DURATION),
+    ];
+    for line in lines {
+        let text = Text::from_section(
+            line,
+            TextStyle {
+                font: assets.font.clone(),
+                font_size: 28.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            },
+        );
+        let style = Style {
+            align_self: AlignSelf::Center,
+            margin: UiRect::vertical(Val::Px(4.0)),
+            ..default()
+        };
+        let line = outlined_text(&mut commands, text, style, settings.text_outline, ());
+        commands.entity(line).set_parent(node);
+    }
+    menu_button(
+        &mut commands,
+        node,
+        BackButton,
+        "Back",
+        &assets.font,
+        &palette,
+        &settings,
+    );
+}
+
+/// List the assets which failed to load
+fn setup_load_error_screen(
+    mut commands: Commands,
+    assets: Res<Assets>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    errors: Res<LoadErrors>,
+) {
+    commands.spawn((Camera2dBundle::default(), OnLoadErrorScreen));
+    let node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(90.0),
+                padding: UiRect::new(Val::Auto, Val::Auto, Val::Px(10.0), Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(OnLoadErrorScreen)
+        .id();
+    let lines = std::iter::once("Failed to load:".to_string()).chain(errors.0.iter().cloned());
+    for line in lines {
+        let text = Text::from_section(
+            line,
+            TextStyle {
+                font: assets.font.clone(),
+                font_size: 28.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            },
+        );
+        let style = Style {
+            align_self: AlignSelf::Center,
+            ..default()
+        };
+        let line = outlined_text(&mut commands, text, style, settings.text_outline, ());
+        commands.entity(line).set_parent(node);
+    }
+    menu_button(
+        &mut commands,
+        node,
+        RetryButton,
+        "Retry",
+        &assets.font,
+        &palette,
+        &settings,
+    );
+}
+
+/// Reload the failed assets and show the splash screen again
+#[allow(clippy::type_complexity)]
+fn retry_button_system(
+    mut interaction_query: Query<
+        (&Interaction, &mut ButtonColorTween),
+        (Changed<Interaction>, With<RetryButton>),
+    >,
+    mut app_state: ResMut<NextState<GameState>>,
+    palette: Res<Palette>,
+    server: Res<AssetServer>,
+    errors: Res<LoadErrors>,
+) {
+    for (interaction, mut tween) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                tween.retarget(palette.button_pressed());
+                for path in &errors.0 {
+                    server.reload(path.clone());
+                }
+                app_state.set(GameState::Loading);
+            }
+            Interaction::Hovered => {
+                tween.retarget(palette.button_hovered());
+            }
+            Interaction::None => {
+                tween.retarget(palette.button_normal());
+            }
+        }
+    }
+}
+
+/// Show the best sessions of all played games
+fn setup_leaderboard_screen(
+    mut commands: Commands,
+    assets: Res<Assets>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    leaderboard: Res<Leaderboard>,
+) {
+    commands.spawn((Camera2dBundle::default(), OnLeaderboardScreen));
+    let node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(90.0),
+                padding: UiRect::new(Val::Auto, Val::Auto, Val::Px(10.0), Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(OnLeaderboardScreen)
+        .id();
+    let mut lines: Vec<_> = leaderboard
+        .0
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let target = match entry.target_met {
+                Some(true) => "  won",
+                Some(false) => "  missed",
+                None => "",
+            };
+            format!(
+                "{}. {}  {:?}{}  {}",
+                i + 1,
+                entry.score,
+                entry.mode,
+                target,
+                entry.date
+            )
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push("No scores yet".to_string());
+    }
+    for line in lines {
+        let text = Text::from_section(
+            line,
+            TextStyle {
+                font: assets.font.clone(),
+                font_size: 28.0,
+                color: Color::rgb(0.9, 0.9, 0.9),
+            },
+        );
+        let style = Style {
+            align_self: AlignSelf::Center,
+            ..default()
+        };
+        let line = outlined_text(&mut commands, text, style, settings.text_outline, ());
+        commands.entity(line).set_parent(node);
+    }
+    menu_button(
+        &mut commands,
+        node,
+        BackButton,
+        "Back",
+        &assets.font,
+        &palette,
+        &settings,
+    );
+}
+
+/// Show the settings which can be changed
+fn setup_settings_screen(
+    mut commands: Commands,
+    assets: Res<Assets>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+) {
+    commands.spawn((Camera2dBundle::default(), OnSettingsScreen));
+    let node = commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(90.0),
+                padding: UiRect::new(Val::Auto, Val::Auto, Val::Px(10.0), Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(OnSettingsScreen)
+        .id();
+    let text = Text::from_section(
+        volume_label(&settings),
+        TextStyle {
+            font: assets.font.clone(),
+            font_size: 36.0,
+            color: Color::rgb(0.9, 0.9, 0.9),
+        },
+    );
+    let style = Style {
+        align_self: AlignSelf::Center,
+        ..default()
+    };
+    let volume = outlined_text(
+        &mut commands,
+        text,
+        style,
+        settings.text_outline,
+        VolumeText,
+    );
+    commands.entity(volume).set_parent(node);
+    for button in SettingsButton::ALL.iter() {
+        menu_button(
+            &mut commands,
+            node,
+            *button,
+            button.label(&settings),
+            &assets.font,
+            &palette,
+            &settings,
+        );
+    }
+    menu_button(
+        &mut commands,
+        node,
+        BackButton,
+        "Back",
+        &assets.font,
+        &palette,
+        &settings,
+    );
+}
+
+/// Bar chart of the reaction times of the session
+fn reaction_histogram(
+    commands: &mut Commands,
+    parent: Entity,
+    reaction_times: &ReactionTimes,
+    font: Handle<Font>,
+) {
+    let text_style = TextStyle {
+        font,
+        font_size: 18.0,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+    let chart = commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(80.0),
+                height: Val::Percent(30.0),
+                margin: UiRect::horizontal(Val::Auto),
+                justify_content: JustifyContent::SpaceEvenly,
+                align_items: AlignItems::FlexEnd,
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+            ..default()
+        })
+        .set_parent(parent)
+        .id();
+    if reaction_times.0.is_empty() {
+        commands
+            .spawn(
+                TextBundle::from_section("No tiles cleared", text_style).with_style(Style {
+                    align_self: AlignSelf::Center,
+                    ..default()
+                }),
+            )
+            .set_parent(chart);
+        return;
+    }
+    let buckets = reaction_times.histogram();
+    let max = buckets.iter().copied().max().unwrap_or(1).max(1);
+    let width = ReactionTimes::BUCKET_WIDTH.as_millis();
+    for (i, count) in buckets.iter().enumerate() {
+        let label = if i == ReactionTimes::BUCKETS - 1 {
+            format!(">{}", i as u128 * width)
+        } else {
+            format!("<{}", (i as u128 + 1) * width)
+        };
+        let column = commands
+            .spawn(NodeBundle {
+                style: Style {
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::FlexEnd,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
                 ..default()
             })
-            .insert(*button)
-            .set_parent(ui_node)
+            .set_parent(chart)
             .id();
         commands
             .spawn(TextBundle::from_section(
-                format!("{:?}", button),
-                TextStyle {
-                    font: assets.font.clone(),
-                    font_size: 40.0,
-                    color: Color::rgb(0.9, 0.9, 0.9),
-                },
+                count.to_string(),
+                text_style.clone(),
             ))
-            .set_parent(button_frame);
+            .set_parent(column);
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    width: Val::Px(30.0),
+                    height: Val::Percent(60.0 * *count as f32 / max as f32),
+                    ..default()
+                },
+                background_color: Color::rgb(0.5, 0.5, 0.8).into(),
+                ..default()
+            })
+            .set_parent(column);
+        commands
+            .spawn(TextBundle::from_section(label, text_style.clone()))
+            .set_parent(column);
+    }
+}
+
+/// Keep the labels of the main menu buttons up to date
+fn update_main_menu_labels(
+    difficulty: Res<Difficulty>,
+    board: Res<GridConfig>,
+    buttons: Query<(&MainMenuButton, &Children)>,
+    nodes: Query<&Children>,
+    mut texts: Query<&mut Text>,
+) {
+    if !difficulty.is_changed() && !board.is_changed() {
+        return;
+    }
+    for (button, children) in &buttons {
+        let label = button.label(*difficulty, *board);
+        set_button_label(children, &label, &nodes, &mut texts);
+    }
+}
+
+/// Recolor all buttons when the palette changes
+fn apply_palette(
+    palette: Res<Palette>,
+    mut buttons: Query<(&Interaction, &mut ButtonColorTween, &mut BorderColor), With<Button>>,
+) {
+    if !palette.is_changed() {
+        return;
+    }
+    for (interaction, mut tween, mut border) in &mut buttons {
+        tween.retarget(match interaction {
+            Interaction::Pressed => palette.button_pressed(),
+            Interaction::Hovered => palette.button_hovered(),
+            Interaction::None => palette.button_normal(),
+        });
+        *border = palette.button_border().into();
     }
 }
 
 /// Handle the menu buttons
-#[allow(clippy::type_complexity)]
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
 fn main_menu_button_system(
+    mut commands: Commands,
     mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor, &MainMenuButton),
+        (&Interaction, &mut ButtonColorTween, &MainMenuButton),
         Changed<Interaction>,
     >,
     mut app_state: ResMut<NextState<GameState>>,
+    palette: Res<Palette>,
+    mut mode: ResMut<GameMode>,
+    mut difficulty: ResMut<Difficulty>,
+    mut settings: ResMut<Settings>,
+    backend: Res<SettingsBackend>,
+    mut board: ResMut<GridConfig>,
+    #[cfg(not(target_arch = "wasm32"))] mut exit: EventWriter<bevy::app::AppExit>,
 ) {
-    for (interaction, mut color, button) in &mut interaction_query {
+    for (interaction, mut tween, button) in &mut interaction_query {
         match *interaction {
             Interaction::Pressed => {
-                *color = PRESSED_BUTTON.into();
+                tween.retarget(palette.button_pressed());
                 info!("Entry selected: {:?}", button);
                 match button {
-                    MainMenuButton::Start => app_state.set(GameState::Game),
+                    MainMenuButton::Start
+                    | MainMenuButton::Target
+                    | MainMenuButton::Endless
+                    | MainMenuButton::Practice
+                    | MainMenuButton::Zen => start_session(*button, &mut mode, &mut commands),
+                    MainMenuButton::Difficulty => {
+                        *difficulty = difficulty.next();
+                        settings.difficulty = *difficulty;
+                        backend.0.save(&settings);
+                    }
+                    MainMenuButton::Board => *board = board.next(),
+                    MainMenuButton::Stats => app_state.set(GameState::Stats),
+                    MainMenuButton::Leaderboard => app_state.set(GameState::Leaderboard),
+                    MainMenuButton::Help => app_state.set(GameState::Help),
+                    MainMenuButton::Settings => app_state.set(GameState::Settings),
+                    #[cfg(not(target_arch = "wasm32"))]
+                    MainMenuButton::Quit => {
+                        exit.send(bevy::app::AppExit);
+                    }
+                }
+            }
+            Interaction::Hovered => {
+                tween.retarget(palette.button_hovered());
+            }
+            Interaction::None => {
+                tween.retarget(palette.button_normal());
+            }
+        }
+    }
+}
+
+/// Start a timed session with [`Action::Confirm`], like [`MainMenuButton::Start`]
+fn start_with_keyboard(
+    keys: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut commands: Commands,
+    mut mode: ResMut<GameMode>,
+) {
+    if bindings.just_pressed(&keys, Action::Confirm) {
+        info!("Entry selected with keyboard: {:?}", MainMenuButton::Start);
+        start_session(MainMenuButton::Start, &mut mode, &mut commands);
+    }
+}
+
+/// Start a session in the mode of one of the mode buttons of the main menu.
+/// The [`Transition`] enters [`GameState::Game`] once the menu faded out.
+fn start_session(button: MainMenuButton, mode: &mut GameMode, commands: &mut Commands) {
+    *mode = match button {
+        MainMenuButton::Target => GameMode::Target,
+        MainMenuButton::Endless => GameMode::Endless,
+        MainMenuButton::Practice => GameMode::Practice,
+        MainMenuButton::Zen => GameMode::Zen,
+        _ => GameMode::Timed,
+    };
+    commands.insert_resource(Transition::new(FadeDirection::Out));
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            focus_policy: bevy::ui::FocusPolicy::Block,
+            z_index: ZIndex::Global(i32::MAX),
+            ..default()
+        },
+        TransitionOverlay,
+    ));
+}
+
+/// Fade the [`TransitionOverlay`], switch to [`GameState::Game`] halfway and clean up at the end
+fn run_transition(
+    mut commands: Commands,
+    mut transition: ResMut<Transition>,
+    time: Res<Time<Real>>,
+    mut app_state: ResMut<NextState<GameState>>,
+    mut overlays: Query<(Entity, &mut BackgroundColor), With<TransitionOverlay>>,
+) {
+    transition.timer.tick(time.delta());
+    for (_, mut color) in &mut overlays {
+        color.0 = Color::BLACK.with_a(transition.alpha());
+    }
+    if !transition.timer.finished() {
+        return;
+    }
+    match transition.direction {
+        FadeDirection::Out => {
+            app_state.set(GameState::Game);
+            *transition = Transition::new(FadeDirection::In);
+        }
+        FadeDirection::In => {
+            for (entity, _) in &overlays {
+                commands.entity(entity).despawn_recursive();
+            }
+            commands.remove_resource::<Transition>();
+        }
+    }
+}
+
+/// Handle the buttons of the settings menu
+fn settings_button_system(
+    mut interaction_query: Query<
+        (&Interaction, &mut ButtonColorTween, &SettingsButton),
+        Changed<Interaction>,
+    >,
+    mut palette: ResMut<Palette>,
+    mut settings: ResMut<Settings>,
+) {
+    for (interaction, mut tween, button) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                tween.retarget(palette.button_pressed());
+                match button {
+                    SettingsButton::Quieter => settings.step_volume(-1.0),
+                    SettingsButton::Louder => settings.step_volume(1.0),
+                    SettingsButton::Accent => palette.next_accent(),
+                    SettingsButton::Pattern => {
+                        settings.background_pattern = settings.background_pattern.next()
+                    }
+                    SettingsButton::Scheme => {
+                        settings.color_scheme = settings.color_scheme.next();
+                        palette.set_scheme(settings.color_scheme);
+                    }
+                    SettingsButton::Font => settings.font = settings.font.next(),
+                    SettingsButton::Spawns => {
+                        settings.spawn_pattern = settings.spawn_pattern.next()
+                    }
                 }
             }
             Interaction::Hovered => {
-                *color = HOVERED_BUTTON.into();
+                tween.retarget(palette.button_hovered());
             }
             Interaction::None => {
-                *color = NORMAL_BUTTON.into();
+                tween.retarget(palette.button_normal());
             }
         }
     }
 }
 
-/// Handle the button interactions of the menu.
+/// Keep the labels of the settings menu up to date
+fn update_settings_labels(
+    settings: Res<Settings>,
+    buttons: Query<(&SettingsButton, &Children)>,
+    nodes: Query<&Children>,
+    mut texts: Query<&mut Text>,
+    volume_texts: Query<Entity, With<VolumeText>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for (button, children) in &buttons {
+        set_button_label(children, &button.label(&settings), &nodes, &mut texts);
+    }
+    for entity in &volume_texts {
+        if let Ok(mut text) = texts.get_mut(entity) {
+            text.sections[0].value = volume_label(&settings);
+        }
+    }
+}
+
+/// Text showing the [`Settings::volume`]
+fn volume_label(settings: &Settings) -> String {
+    format!("Volume: {:.0}%", settings.volume * 100.0)
+}
+
+/// Store the settings when leaving the settings menu
+fn save_settings(settings: Res<Settings>, backend: Res<SettingsBackend>) {
+    backend.0.save(&settings);
+}
+
+/// Go back to the main menu
+#[allow(clippy::type_complexity)]
+fn back_button_system(
+    mut interaction_query: Query<
+        (&Interaction, &mut ButtonColorTween),
+        (Changed<Interaction>, With<BackButton>),
+    >,
+    mut app_state: ResMut<NextState<GameState>>,
+    palette: Res<Palette>,
+) {
+    for (interaction, mut tween) in &mut interaction_query {
+        match *interaction {
+            Interaction::Pressed => {
+                tween.retarget(palette.button_pressed());
+                app_state.set(GameState::Menu);
+            }
+            Interaction::Hovered => {
+                tween.retarget(palette.button_hovered());
+            }
+            Interaction::None => {
+                tween.retarget(palette.button_normal());
+            }
+        }
+    }
+}
+
+/// Handle the button interactions of the menu, R and M act as the restart and menu buttons.
 /// Possible options are defined inside [`GameMenuButton`].
+/// With [`Settings::confirm_quit`] the menu button has to be pressed twice to leave.
+#[allow(clippy::too_many_arguments)]
 fn game_menu_button_system(
     mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor, &GameMenuButton),
+        (&Interaction, &mut ButtonColorTween, &GameMenuButton),
         Changed<Interaction>,
     >,
     mut game_state: ResMut<NextState<GameState>>,
     mut running_state: ResMut<NextState<RunningState>>,
     mut delay: ResMut<MenuActiveDelay>,
     time: ResMut<Time<Real>>,
+    palette: Res<Palette>,
+    settings: Res<Settings>,
+    mut quit_confirm: Local<Option<Timer>>,
+    buttons: Query<(&GameMenuButton, &Children)>,
+    nodes: Query<&Children>,
+    mut texts: Query<&mut Text>,
+    (keys, bindings): (Res<ButtonInput<KeyCode>>, Res<KeyBindings>),
 ) {
     delay.0.tick(time.delta());
     if !delay.0.finished() {
         return;
     }
-    for (interaction, mut color, button) in &mut interaction_query {
+    let mut set_menu_label = |label: &str| {
+        let menu = buttons
+            .iter()
+            .filter(|(button, _)| **button == GameMenuButton::Menu);
+        for (_, children) in menu {
+            set_button_label(children, label, &nodes, &mut texts);
+        }
+    };
+    // Revert the menu button when the quit is not confirmed in time
+    if let Some(timer) = quit_confirm.as_mut() {
+        if timer.tick(time.delta()).finished() {
+            *quit_confirm = None;
+            set_menu_label(&format!("{:?}", GameMenuButton::Menu));
+        }
+    }
+    let mut selected = if bindings.just_pressed(&keys, Action::Restart) {
+        Some(GameMenuButton::Restart)
+    } else if keys.just_pressed(KeyCode::KeyM) {
+        Some(GameMenuButton::Menu)
+    } else {
+        None
+    };
+    for (interaction, mut tween, button) in &mut interaction_query {
         match *interaction {
             Interaction::Pressed => {
-                *color = PRESSED_BUTTON.into();
-                info!("Entry selected: {:?}", button);
-                match button {
-                    GameMenuButton::Restart => {
-                        running_state.set(RunningState::Running);
-                    }
-                    GameMenuButton::Menu => game_state.set(GameState::Menu),
-                }
+                tween.retarget(palette.button_pressed());
+                selected = Some(*button);
             }
             Interaction::Hovered => {
-                *color = HOVERED_BUTTON.into();
+                tween.retarget(palette.button_hovered());
             }
             Interaction::None => {
-                *color = NORMAL_BUTTON.into();
+                tween.retarget(palette.button_normal());
             }
         }
     }
+    let Some(button) = selected else {
+        return;
+    };
+    info!("Entry selected: {:?}", button);
+    match button {
+        GameMenuButton::Restart => {
+            *quit_confirm = None;
+            running_state.set(RunningState::Countdown);
+        }
+        GameMenuButton::Menu if settings.confirm_quit && quit_confirm.is_none() => {
+            *quit_confirm = Some(Timer::from_seconds(2.0, TimerMode::Once));
+            set_menu_label("Press again");
+        }
+        GameMenuButton::Menu => {
+            *quit_confirm = None;
+            game_state.set(GameState::Menu);
+        }
+    }
 }